@@ -0,0 +1,148 @@
+use jiff::Timestamp;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Units accepted in a minimum age, from largest to smallest, with their length in seconds.
+const UNITS: [(&str, u64); 4] = [("w", 7 * 86400), ("d", 86400), ("h", 3600), ("m", 60)];
+
+/// How long a version has to have been published before it is adopted, e.g. `3d`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct MinimumAge {
+    amount: u64,
+    unit: &'static str,
+}
+
+#[derive(Debug, Error)]
+pub enum ParseMinimumAgeError {
+    #[error("age must be 0, or a whole number followed by a unit, one of w, d, h or m (e.g. 3d)")]
+    InvalidFormat,
+}
+
+impl MinimumAge {
+    pub fn duration(self) -> Duration {
+        let unit_secs = UNITS
+            .iter()
+            .find(|(unit, _)| *unit == self.unit)
+            .map_or(0, |(_, secs)| *secs);
+
+        Duration::from_secs(self.amount.saturating_mul(unit_secs))
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.amount == 0
+    }
+
+    /// Whether a version published at `published_at` is old enough to be adopted.
+    pub fn is_reached_by(self, published_at: Timestamp) -> bool {
+        age_of(published_at) >= self.duration()
+    }
+}
+
+impl FromStr for MinimumAge {
+    type Err = ParseMinimumAgeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Zero needs no unit, e.g. to turn off a minimum age passed to `update`
+        if s == "0" {
+            return Ok(Self {
+                amount: 0,
+                unit: "d",
+            });
+        }
+
+        let split = s
+            .find(|c: char| !c.is_ascii_digit())
+            .ok_or(ParseMinimumAgeError::InvalidFormat)?;
+        let (amount, unit) = s.split_at(split);
+
+        let amount = amount
+            .parse()
+            .map_err(|_| ParseMinimumAgeError::InvalidFormat)?;
+        let (unit, _) = UNITS
+            .iter()
+            .find(|(u, _)| *u == unit)
+            .ok_or(ParseMinimumAgeError::InvalidFormat)?;
+
+        Ok(Self { amount, unit })
+    }
+}
+
+impl fmt::Display for MinimumAge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.amount, self.unit)
+    }
+}
+
+impl Serialize for MinimumAge {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for MinimumAge {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// How long ago `timestamp` was, or zero if it lies in the future.
+pub fn age_of(timestamp: Timestamp) -> Duration {
+    Duration::try_from(Timestamp::now().duration_since(timestamp)).unwrap_or_default()
+}
+
+/// Formats a duration with its two largest units, e.g. `2d 5h`.
+pub fn format_age(age: Duration) -> String {
+    let mut secs = age.as_secs();
+    let mut parts = Vec::new();
+
+    for (unit, unit_secs) in UNITS {
+        if secs >= unit_secs {
+            parts.push(format!("{}{unit}", secs / unit_secs));
+            secs %= unit_secs;
+        }
+    }
+
+    if parts.is_empty() {
+        return "0m".to_string();
+    }
+
+    parts.truncate(2);
+    parts.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minimum_age_needs_a_unit() {
+        let age = "3d".parse::<MinimumAge>().unwrap();
+        assert_eq!(age.duration(), Duration::from_secs(3 * 86400));
+        assert_eq!(age.to_string(), "3d");
+
+        assert_eq!(
+            "2w".parse::<MinimumAge>().unwrap().duration(),
+            Duration::from_secs(14 * 86400)
+        );
+        assert!("0".parse::<MinimumAge>().unwrap().is_zero());
+
+        for invalid in ["3", "d", "3 d", "3days", "-1d", "1.5h", ""] {
+            assert!(invalid.parse::<MinimumAge>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn ages_are_formatted_with_two_units() {
+        assert_eq!(
+            format_age(Duration::from_secs(2 * 86400 + 5 * 3600 + 59)),
+            "2d 5h"
+        );
+        assert_eq!(format_age(Duration::from_secs(90)), "1m");
+        assert_eq!(format_age(Duration::from_secs(59)), "0m");
+    }
+}
@@ -0,0 +1,18 @@
+use url::Url;
+
+/// Parses an artifact URL template, checking that it is a URL
+/// while keeping placeholders such as `{version}` as they are.
+pub fn validate_artifact_url(s: &str) -> Result<String, String> {
+    Url::parse(s).map_err(|e| e.to_string())?;
+
+    Ok(s.to_string())
+}
+
+/// Parses a mapping of the form `FROM=TO`, such as `x86_64=amd64` or `NAME=URL`.
+pub fn parse_mapping(s: &str) -> Result<(String, String), String> {
+    let (from, to) = s
+        .split_once('=')
+        .ok_or_else(|| format!("'{s}' is not of the form FROM=TO"))?;
+
+    Ok((from.to_string(), to.to_string()))
+}
@@ -0,0 +1,155 @@
+use crate::http::{get_json, get_text, HttpGetError};
+use crate::releases::LatestRelease;
+use crate::tags::{parse_version, TagFilter};
+use jiff::Timestamp;
+use serde::Deserialize;
+use thiserror::Error;
+use url::Url;
+
+pub const DEFAULT_CRATES_INDEX_URL: &str = "https://index.crates.io";
+
+/// Markers that may appear in the download URL of a registry,
+/// as described in the registry index format.
+const DOWNLOAD_MARKERS: [&str; 5] = [
+    "{crate}",
+    "{version}",
+    "{prefix}",
+    "{lowerprefix}",
+    "{sha256-checksum}",
+];
+
+/// A line of a crate's file in a sparse index, describing one version.
+#[derive(Deserialize)]
+struct IndexEntry {
+    vers: String,
+    #[serde(default)]
+    yanked: bool,
+    /// Only set for versions published since registries started recording it
+    pubtime: Option<Timestamp>,
+}
+
+#[derive(Deserialize)]
+struct RegistryConfig {
+    dl: String,
+}
+
+#[derive(Debug, Error)]
+pub enum FetchLatestCrateError {
+    #[error("could not build index URL: {0}")]
+    InvalidIndexUrl(#[from] url::ParseError),
+    #[error("failed to fetch index: {0}")]
+    Http(#[from] HttpGetError),
+    #[error("index file of the crate is malformed: {0}")]
+    MalformedIndexEntry(#[from] serde_json::Error),
+    #[error("no version fits the filter")]
+    NoMatchingVersion,
+    #[error("download URL {0} of the registry depends on the checksum of every version")]
+    ChecksumInDownloadUrl(String),
+}
+
+fn index_base(registry_url: Option<&Url>) -> String {
+    match registry_url {
+        Some(url) => url.as_str().trim_end_matches('/').to_string(),
+        None => DEFAULT_CRATES_INDEX_URL.to_string(),
+    }
+}
+
+/// The directories a crate's index file is in, e.g. `se/rd` for `serde` or `3/s` for `syn`.
+fn index_prefix(name: &str) -> String {
+    match name.len() {
+        1 => "1".to_string(),
+        2 => "2".to_string(),
+        3 => format!("3/{}", &name[..1]),
+        _ => format!("{}/{}", &name[..2], &name[2..4]),
+    }
+}
+
+/// Gets the newest version of a crate from the sparse index of a registry that passes `filter`,
+/// ignoring yanked versions.
+///
+/// `registry_url` is the base URL of the sparse index, defaulting to the one of crates.io.
+pub fn fetch_latest_crate_version(
+    registry_url: Option<&Url>,
+    name: &str,
+    filter: &TagFilter,
+) -> Result<LatestRelease, FetchLatestCrateError> {
+    // Index paths are lowercase, whatever the case of the crate name
+    let name = name.to_lowercase();
+    let url = Url::parse(&format!(
+        "{}/{}/{name}",
+        index_base(registry_url),
+        index_prefix(&name)
+    ))?;
+
+    let mut newest = None;
+    for line in get_text(&url)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+    {
+        let entry: IndexEntry = serde_json::from_str(line)?;
+        if entry.yanked {
+            continue;
+        }
+        let Some(parsed) = parse_version(&entry.vers) else {
+            continue;
+        };
+        let Some(version) = filter.version_from_release_tag(&entry.vers, !parsed.pre.is_empty())
+        else {
+            continue;
+        };
+
+        if newest.as_ref().is_none_or(|(newest, _)| parsed > *newest) {
+            newest = Some((
+                parsed,
+                LatestRelease {
+                    version,
+                    published_at: entry.pubtime,
+                    integrity: None,
+                    artifact_url: None,
+                    rev: None,
+                },
+            ));
+        }
+    }
+
+    newest
+        .map(|(_, release)| release)
+        .ok_or(FetchLatestCrateError::NoMatchingVersion)
+}
+
+/// Builds the URL template of a crate's `.crate` files from the download URL in the registry's config,
+/// with `{version}` left as a placeholder.
+pub fn crate_download_url_template(
+    registry_url: Option<&Url>,
+    name: &str,
+) -> Result<String, FetchLatestCrateError> {
+    let config_url = Url::parse(&format!("{}/config.json", index_base(registry_url)))?;
+    let config: RegistryConfig = get_json(&config_url, &[])?;
+    let dl = config.dl.trim_end_matches('/');
+
+    if !DOWNLOAD_MARKERS.iter().any(|marker| dl.contains(marker)) {
+        return Ok(format!("{dl}/{name}/{{version}}/download"));
+    }
+    if dl.contains("{sha256-checksum}") {
+        return Err(FetchLatestCrateError::ChecksumInDownloadUrl(config.dl));
+    }
+
+    Ok(dl
+        .replace("{crate}", name)
+        .replace("{lowerprefix}", &index_prefix(&name.to_lowercase()))
+        .replace("{prefix}", &index_prefix(name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_prefix_depends_on_name_length() {
+        assert_eq!(index_prefix("a"), "1");
+        assert_eq!(index_prefix("cc"), "2");
+        assert_eq!(index_prefix("syn"), "3/s");
+        assert_eq!(index_prefix("serde"), "se/rd");
+        assert_eq!(index_prefix("toml"), "to/ml");
+    }
+}
@@ -0,0 +1,230 @@
+use crate::http::{get_text, HttpGetError};
+use crate::releases::LatestRelease;
+use crate::tags::{compare_versions, TagFilter, VersionPattern};
+use clap::ValueEnum;
+use jiff::fmt::rfc2822::DateTimeParser;
+use jiff::Timestamp;
+use roxmltree::{Document, Node};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use url::Url;
+
+/// Pattern used when none is given, finding the first dotted version number,
+/// along with a prerelease suffix such as `-rc1`.
+pub const DEFAULT_FEED_VERSION_PATTERN: &str =
+    r"(?<version>[0-9]+(?:\.[0-9]+)+(?:-[0-9A-Za-z]+(?:\.[0-9A-Za-z]+)*)?)";
+
+/// The part of a feed entry that versions are found in.
+#[derive(Clone, Copy, Default, Deserialize, PartialEq, Serialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum FeedField {
+    /// The title of the entry, e.g. the name of a release
+    #[default]
+    Title,
+    /// The link of the entry, e.g. `https://github.com/owner/repo/releases/tag/v1.2.3`
+    Link,
+}
+
+/// An entry of an RSS or Atom feed.
+struct FeedEntry {
+    title: Option<String>,
+    link: Option<String>,
+    published_at: Option<Timestamp>,
+}
+
+#[derive(Debug, Error)]
+pub enum FetchLatestFeedVersionError {
+    #[error("failed to fetch feed: {0}")]
+    Http(#[from] HttpGetError),
+    #[error("feed is not valid XML: {0}")]
+    MalformedFeed(#[from] roxmltree::Error),
+    #[error("no entry of the feed has a version that fits the filter")]
+    NoMatchingVersion,
+}
+
+/// The text of the first child element of `node` with one of `names`, ignoring namespaces.
+fn child_text(node: Node, names: &[&str]) -> Option<String> {
+    names.iter().find_map(|name| {
+        node.children()
+            .find(|child| child.tag_name().name() == *name)
+            .and_then(|child| child.text())
+            .map(|text| text.trim().to_string())
+    })
+}
+
+/// Parses the date of an entry, which is RFC 2822 in RSS (e.g. `Tue, 10 Jun 2003 04:00:00 GMT`)
+/// and RFC 3339 in Atom and Dublin Core (e.g. `2003-12-13T18:30:02Z`).
+fn parse_date(date: &str) -> Option<Timestamp> {
+    date.parse()
+        .ok()
+        .or_else(|| DateTimeParser::new().parse_timestamp(date).ok())
+}
+
+/// Reads the entries of an RSS 2.0, RSS 1.0 or Atom feed.
+fn parse_entries(feed: &str) -> Result<Vec<FeedEntry>, roxmltree::Error> {
+    let document = Document::parse(feed)?;
+
+    let entries = document
+        .descendants()
+        .filter(|node| matches!(node.tag_name().name(), "item" | "entry"))
+        .map(|entry| {
+            // Atom links are in an attribute, preferring the `alternate` one if there are several
+            let atom_link = entry
+                .children()
+                .filter(|child| child.tag_name().name() == "link")
+                .filter_map(|link| Some((link.attribute("rel"), link.attribute("href")?)))
+                .min_by_key(|(rel, _)| rel.is_some_and(|rel| rel != "alternate"))
+                .map(|(_, href)| href.to_string());
+
+            FeedEntry {
+                title: child_text(entry, &["title"]),
+                link: atom_link.or_else(|| child_text(entry, &["link"])),
+                published_at: child_text(entry, &["published", "pubDate", "date", "updated"])
+                    .and_then(|date| parse_date(&date)),
+            }
+        })
+        .collect();
+
+    Ok(entries)
+}
+
+/// Gets the newest version in an RSS or Atom feed, along with the date of its entry.
+///
+/// The version of an entry is the first one `pattern` finds in its `field`,
+/// and versions are ordered like the tags of `git-tags` sources rather than by date,
+/// so that e.g. a patch release of an older branch doesn't count as the newest one.
+pub fn fetch_latest_feed_version(
+    feed_url: &Url,
+    pattern: &VersionPattern,
+    field: FeedField,
+    filter: &TagFilter,
+) -> Result<LatestRelease, FetchLatestFeedVersionError> {
+    let entries = parse_entries(&get_text(feed_url)?)?;
+
+    entries
+        .into_iter()
+        .filter_map(|entry| {
+            let text = match field {
+                FeedField::Title => entry.title.as_deref()?,
+                FeedField::Link => entry.link.as_deref()?,
+            };
+            // Only the version itself is checked for prerelease markers,
+            // since free-form titles use words such as `next` or `dev` in other senses
+            let version = filter.version_from_tag(pattern.versions_in(text).next()?)?;
+
+            Some(LatestRelease {
+                version,
+                published_at: entry.published_at,
+                integrity: None,
+                artifact_url: None,
+                rev: None,
+            })
+        })
+        .max_by(|a, b| compare_versions(&a.version, &b.version))
+        .ok_or(FetchLatestFeedVersionError::NoMatchingVersion)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RSS: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0">
+  <channel>
+    <title>Releases</title>
+    <link>https://example.com/releases</link>
+    <item>
+      <title><![CDATA[Tool 1.2.0 released]]></title>
+      <link>https://example.com/releases/1.2.0</link>
+      <pubDate>Tue, 10 Jun 2003 04:00:00 GMT</pubDate>
+    </item>
+    <item>
+      <title>Tool 1.1.0 released</title>
+    </item>
+  </channel>
+</rss>"#;
+
+    const ATOM: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Releases</title>
+  <link href="https://example.com/"/>
+  <entry>
+    <title>v2.0.0</title>
+    <link rel="enclosure" href="https://example.com/tool-2.0.0.tar.gz"/>
+    <link rel="alternate" href="https://example.com/releases/tag/v2.0.0"/>
+    <updated>2003-12-13T18:30:02Z</updated>
+  </entry>
+  <entry>
+    <title>v1.9.0</title>
+    <link href="https://example.com/releases/tag/v1.9.0"/>
+    <published>2003-11-01T08:00:00+02:00</published>
+    <updated>2003-12-01T08:00:00Z</updated>
+  </entry>
+</feed>"#;
+
+    #[test]
+    fn rss_items_are_read() {
+        let entries = parse_entries(RSS).unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].title.as_deref(), Some("Tool 1.2.0 released"));
+        assert_eq!(
+            entries[0].link.as_deref(),
+            Some("https://example.com/releases/1.2.0")
+        );
+        assert_eq!(
+            entries[0].published_at,
+            Some("2003-06-10T04:00:00Z".parse().unwrap())
+        );
+        assert_eq!(entries[1].link, None);
+        assert_eq!(entries[1].published_at, None);
+    }
+
+    #[test]
+    fn atom_entries_prefer_alternate_links_and_publication_dates() {
+        let entries = parse_entries(ATOM).unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].title.as_deref(), Some("v2.0.0"));
+        assert_eq!(
+            entries[0].link.as_deref(),
+            Some("https://example.com/releases/tag/v2.0.0")
+        );
+        assert_eq!(
+            entries[0].published_at,
+            Some("2003-12-13T18:30:02Z".parse().unwrap())
+        );
+        assert_eq!(
+            entries[1].link.as_deref(),
+            Some("https://example.com/releases/tag/v1.9.0")
+        );
+        assert_eq!(
+            entries[1].published_at,
+            Some("2003-11-01T06:00:00Z".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn rss_1_dates_are_read_from_dublin_core() {
+        let feed = r#"<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+            xmlns="http://purl.org/rss/1.0/" xmlns:dc="http://purl.org/dc/elements/1.1/">
+          <item rdf:about="https://example.com/3.0">
+            <title>3.0</title>
+            <link>https://example.com/3.0</link>
+            <dc:date>2024-01-02T03:04:05Z</dc:date>
+          </item>
+        </rdf:RDF>"#;
+        let entries = parse_entries(feed).unwrap();
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            entries[0].published_at,
+            Some("2024-01-02T03:04:05Z".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn malformed_feeds_are_rejected() {
+        assert!(parse_entries("<rss><channel><item></channel></rss>").is_err());
+    }
+}
@@ -0,0 +1,147 @@
+use serde::de::DeserializeOwned;
+use std::io::{self, Read};
+use thiserror::Error;
+use url::Url;
+
+#[derive(Debug, Error)]
+pub enum HttpGetError {
+    #[error("{url} responded with status code {status}")]
+    Status { url: String, status: u16 },
+    #[error("request to {url} failed: {error}")]
+    Request {
+        url: String,
+        error: Box<ureq::Error>,
+    },
+    #[error("response from {url} is malformed or incorrect json: {error}")]
+    MalformedOrIncorrectJson {
+        url: String,
+        error: Box<ureq::Error>,
+    },
+}
+
+impl HttpGetError {
+    pub fn from_io(url: &Url, error: io::Error) -> Self {
+        Self::Request {
+            url: url.to_string(),
+            error: Box::new(ureq::Error::Io(error)),
+        }
+    }
+
+    fn from_ureq(url: &Url, error: ureq::Error) -> Self {
+        match error {
+            ureq::Error::StatusCode(status) => Self::Status {
+                url: url.to_string(),
+                status,
+            },
+            ureq::Error::Json(_) => Self::MalformedOrIncorrectJson {
+                url: url.to_string(),
+                error: Box::new(error),
+            },
+            _ => Self::Request {
+                url: url.to_string(),
+                error: Box::new(error),
+            },
+        }
+    }
+}
+
+/// Send a GET request to `url` with the given headers and parse the response body as JSON.
+pub fn get_json<T: DeserializeOwned>(
+    url: &Url,
+    headers: &[(&str, &str)],
+) -> Result<T, HttpGetError> {
+    let mut request = ureq::get(url.as_str());
+    for (key, value) in headers {
+        request = request.header(*key, *value);
+    }
+
+    request
+        .call()
+        .and_then(|mut response| response.body_mut().read_json())
+        .map_err(|e| HttpGetError::from_ureq(url, e))
+}
+
+/// Send a GET request to `url` like [`get_json`], for an API whose results are split into pages.
+/// Along with the response, returns the URL of the next page, if there is one on the same host.
+///
+/// The next page is taken from the `Link` header (as GitHub and Gitea send it),
+/// or failing that from the `X-Next-Page` header (as GitLab sends it) as the `page` query parameter.
+pub fn get_json_page<T: DeserializeOwned>(
+    url: &Url,
+    headers: &[(&str, &str)],
+) -> Result<(T, Option<Url>), HttpGetError> {
+    let mut request = ureq::get(url.as_str());
+    for (key, value) in headers {
+        request = request.header(*key, *value);
+    }
+
+    let mut response = request
+        .call()
+        .map_err(|e| HttpGetError::from_ureq(url, e))?;
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+    };
+
+    let next = header("link")
+        .and_then(|link| next_page_from_link(url, link))
+        .or_else(|| {
+            let page = header("x-next-page").filter(|page| !page.is_empty())?;
+            let mut next = url.clone();
+            let query = url
+                .query_pairs()
+                .filter(|(key, _)| key != "page")
+                .collect::<Vec<_>>();
+            next.query_pairs_mut()
+                .clear()
+                .extend_pairs(query)
+                .append_pair("page", page);
+            Some(next)
+        })
+        // Credentials in the headers are only meant for the host of the first page
+        .filter(|next| next.origin() == url.origin());
+
+    let body = response
+        .body_mut()
+        .read_json()
+        .map_err(|e| HttpGetError::from_ureq(url, e))?;
+
+    Ok((body, next))
+}
+
+/// Finds the link to the next page in a `Link` header,
+/// such as `<https://api.github.com/...&page=2>; rel="next", <...>; rel="last"`.
+fn next_page_from_link(url: &Url, link: &str) -> Option<Url> {
+    link.split(',').find_map(|part| {
+        let (target, params) = part.split_once(';')?;
+        let is_next = params.split(';').any(|param| {
+            param
+                .trim()
+                .strip_prefix("rel=")
+                .is_some_and(|rel| rel.trim_matches('"').split(' ').any(|r| r == "next"))
+        });
+        let target = target.trim().strip_prefix('<')?.strip_suffix('>')?;
+
+        is_next.then(|| url.join(target).ok()).flatten()
+    })
+}
+
+/// Send a GET request to `url` and return a reader over the response body.
+///
+/// Unlike [`get_json`], the size of the body is not limited.
+pub fn get_reader(url: &Url) -> Result<impl Read, HttpGetError> {
+    ureq::get(url.as_str())
+        .call()
+        .map(|response| response.into_body().into_reader())
+        .map_err(|e| HttpGetError::from_ureq(url, e))
+}
+
+/// Send a GET request to `url` and return the response body as text.
+pub fn get_text(url: &Url) -> Result<String, HttpGetError> {
+    ureq::get(url.as_str())
+        .call()
+        .and_then(|mut response| response.body_mut().read_to_string())
+        .map_err(|e| HttpGetError::from_ureq(url, e))
+}
@@ -0,0 +1,268 @@
+use crate::http::{get_json, HttpGetError};
+use crate::releases::LatestRelease;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+use url::Url;
+
+/// A step of a JSON path, into either an object or an array.
+#[derive(Clone)]
+enum PathSegment {
+    Key(String),
+    /// An array index, where negative indices count from the end (`-1` is the last element)
+    Index(i64),
+}
+
+/// A path to a value in a JSON document, in a subset of JSONPath and jq syntax:
+/// keys separated by dots and array indices in brackets, such as `$.releases[0].version`
+/// or `.downloads["linux-x64"].url`, where the leading `$` or `.` is optional.
+#[derive(Clone)]
+pub struct JsonPath {
+    path: String,
+    segments: Vec<PathSegment>,
+}
+
+#[derive(Debug, Error)]
+pub enum ParseJsonPathError {
+    #[error("expected a key after '.'")]
+    EmptyKey,
+    #[error("unclosed bracket")]
+    UnclosedBracket,
+    #[error("expected an index or a quoted key in brackets, found [{0}]")]
+    InvalidBracket(String),
+    #[error("unexpected character '{0}' after a bracket; keys start with '.'")]
+    UnexpectedCharacter(char),
+}
+
+impl FromStr for JsonPath {
+    type Err = ParseJsonPathError;
+
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        let rest = path.strip_prefix('$').unwrap_or(path);
+        // A lone `.` is jq's identity, selecting the whole document,
+        // and a path may start with a key without a dot, as in `releases[0]`
+        let rest = match rest {
+            "." => String::new(),
+            rest if rest.is_empty() || rest.starts_with(['.', '[']) => rest.to_string(),
+            rest => format!(".{rest}"),
+        };
+        let mut segments = Vec::new();
+
+        let mut chars = rest.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '.' => {
+                    let mut key = String::new();
+                    while let Some(&c) = chars.peek() {
+                        if c == '.' || c == '[' {
+                            break;
+                        }
+                        key.push(c);
+                        chars.next();
+                    }
+                    if key.is_empty() {
+                        return Err(ParseJsonPathError::EmptyKey);
+                    }
+                    segments.push(PathSegment::Key(key));
+                }
+                '[' => {
+                    let mut inner = String::new();
+                    let mut closed = false;
+                    let mut quote = None;
+                    for c in chars.by_ref() {
+                        match (quote, c) {
+                            (None, ']') => {
+                                closed = true;
+                                break;
+                            }
+                            (None, '"' | '\'') => quote = Some(c),
+                            (Some(q), c) if c == q => quote = None,
+                            _ => {}
+                        }
+                        inner.push(c);
+                    }
+                    if !closed {
+                        return Err(ParseJsonPathError::UnclosedBracket);
+                    }
+
+                    let trimmed = inner.trim();
+                    let quoted = ['"', '\''].iter().find_map(|quote| {
+                        trimmed
+                            .strip_prefix(*quote)
+                            .and_then(|key| key.strip_suffix(*quote))
+                    });
+                    let segment = match quoted {
+                        Some(key) => PathSegment::Key(key.to_string()),
+                        None => PathSegment::Index(
+                            trimmed
+                                .parse()
+                                .map_err(|_| ParseJsonPathError::InvalidBracket(inner.clone()))?,
+                        ),
+                    };
+                    segments.push(segment);
+                }
+                c => return Err(ParseJsonPathError::UnexpectedCharacter(c)),
+            }
+        }
+
+        Ok(Self {
+            path: path.to_string(),
+            segments,
+        })
+    }
+}
+
+impl JsonPath {
+    /// The value at the path in `document`, if every step of it exists.
+    pub fn select<'a>(&self, document: &'a Value) -> Option<&'a Value> {
+        self.segments
+            .iter()
+            .try_fold(document, |value, segment| match segment {
+                PathSegment::Key(key) => value.get(key),
+                PathSegment::Index(index) => {
+                    let array = value.as_array()?;
+                    let index = if *index < 0 {
+                        array.len().checked_sub(index.unsigned_abs() as usize)?
+                    } else {
+                        *index as usize
+                    };
+                    array.get(index)
+                }
+            })
+    }
+}
+
+impl fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.path)
+    }
+}
+
+impl Serialize for JsonPath {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.path)
+    }
+}
+
+impl<'de> Deserialize<'de> for JsonPath {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum FetchHttpJsonVersionError {
+    #[error("failed to fetch endpoint: {0}")]
+    Http(#[from] HttpGetError),
+    #[error("the response has no value at {0}")]
+    PathNotFound(String),
+    #[error("the value at {path} is {kind}, not a string or number")]
+    NotAString { path: String, kind: &'static str },
+}
+
+/// The value at `path` in `document` as a string, accepting numbers for versions such as `2024`.
+fn select_string(document: &Value, path: &JsonPath) -> Result<String, FetchHttpJsonVersionError> {
+    match path.select(document) {
+        Some(Value::String(s)) => Ok(s.clone()),
+        Some(Value::Number(n)) => Ok(n.to_string()),
+        Some(value) => Err(FetchHttpJsonVersionError::NotAString {
+            path: path.to_string(),
+            kind: match value {
+                Value::Object(_) => "an object",
+                Value::Array(_) => "an array",
+                Value::Bool(_) => "a boolean",
+                _ => "null",
+            },
+        }),
+        None => Err(FetchHttpJsonVersionError::PathNotFound(path.to_string())),
+    }
+}
+
+/// Gets the version reported by a JSON endpoint at `version_path`,
+/// along with the URL of its artifact at `artifact_url_path`, if set.
+pub fn fetch_http_json_version(
+    endpoint: &Url,
+    version_path: &JsonPath,
+    artifact_url_path: Option<&JsonPath>,
+) -> Result<LatestRelease, FetchHttpJsonVersionError> {
+    let document: Value = get_json(endpoint, &[("Accept", "application/json")])?;
+
+    Ok(LatestRelease {
+        version: select_string(&document, version_path)?,
+        published_at: None,
+        integrity: None,
+        artifact_url: artifact_url_path
+            .map(|path| select_string(&document, path))
+            .transpose()?,
+        rev: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn select(path: &str, document: &Value) -> Option<Value> {
+        path.parse::<JsonPath>().unwrap().select(document).cloned()
+    }
+
+    #[test]
+    fn path_syntaxes_select_the_same_value() {
+        let document = json!({"releases": [{"version": "1.0"}, {"version": "2.0"}]});
+
+        for path in [
+            "$.releases[1].version",
+            ".releases[1].version",
+            "releases[1].version",
+            "$['releases'][1][\"version\"]",
+        ] {
+            assert_eq!(select(path, &document), Some(json!("2.0")), "{path}");
+        }
+        assert_eq!(select(".", &document), Some(document.clone()));
+        assert_eq!(select("$", &document), Some(document.clone()));
+    }
+
+    #[test]
+    fn quoted_keys_may_contain_dots_and_brackets() {
+        let document = json!({"downloads": {"linux-x64.tar]": {"url": "https://example.com"}}});
+
+        assert_eq!(
+            select(".downloads[\"linux-x64.tar]\"].url", &document),
+            Some(json!("https://example.com"))
+        );
+    }
+
+    #[test]
+    fn negative_indices_count_from_the_end() {
+        let document = json!({"versions": ["1.0", "1.1", "1.2"]});
+
+        assert_eq!(select(".versions[-1]", &document), Some(json!("1.2")));
+        assert_eq!(select(".versions[-3]", &document), Some(json!("1.0")));
+        assert_eq!(select(".versions[-4]", &document), None);
+        assert_eq!(select(".versions[3]", &document), None);
+    }
+
+    #[test]
+    fn malformed_paths_are_rejected() {
+        let parse = |path: &str| path.parse::<JsonPath>().err();
+
+        assert!(matches!(parse(".a..b"), Some(ParseJsonPathError::EmptyKey)));
+        assert!(matches!(
+            parse(".a[0"),
+            Some(ParseJsonPathError::UnclosedBracket)
+        ));
+        assert!(matches!(
+            parse(".a[x]"),
+            Some(ParseJsonPathError::InvalidBracket(_))
+        ));
+        assert!(matches!(
+            parse(".a[0]b"),
+            Some(ParseJsonPathError::UnexpectedCharacter('b'))
+        ));
+    }
+}
@@ -0,0 +1,38 @@
+use crate::http::{get_text, HttpGetError};
+use crate::releases::LatestRelease;
+use crate::tags::{compare_versions, TagFilter, VersionPattern};
+use thiserror::Error;
+use url::Url;
+
+#[derive(Debug, Error)]
+pub enum FetchLatestPageVersionError {
+    #[error("failed to fetch page: {0}")]
+    Http(#[from] HttpGetError),
+    #[error("no match of the pattern on the page fits the filter")]
+    NoMatchingVersion,
+}
+
+/// Gets the newest version on a web page, out of those captured by `pattern` that pass `filter`.
+///
+/// Versions are ordered like the tags of `git-tags` sources,
+/// so that e.g. `1.10` is newer than `1.9` and `2.0` is newer than `2.0-rc1`.
+pub fn fetch_latest_page_version(
+    page_url: &Url,
+    pattern: &VersionPattern,
+    filter: &TagFilter,
+) -> Result<LatestRelease, FetchLatestPageVersionError> {
+    let page = get_text(page_url)?;
+
+    pattern
+        .versions_in(&page)
+        .filter_map(|version| filter.version_from_tag(version))
+        .max_by(|a, b| compare_versions(a, b))
+        .map(|version| LatestRelease {
+            version,
+            published_at: None,
+            integrity: None,
+            artifact_url: None,
+            rev: None,
+        })
+        .ok_or(FetchLatestPageVersionError::NoMatchingVersion)
+}
@@ -1,15 +1,40 @@
+mod age;
+mod args;
+mod crates_io;
+mod feed;
+mod http;
+mod http_json;
+mod http_regex;
 mod logging;
+mod migrations;
+mod nix_expr;
+mod npm;
+mod parallel;
+mod prefetch;
+mod pypi;
+mod releases;
 mod source;
+mod tags;
 mod subcommands {
     pub mod add;
     pub mod delete;
+    pub mod edit;
+    pub mod export;
+    pub mod import;
     pub mod init;
+    pub mod list;
+    pub mod migrate;
+    pub mod show;
     pub mod update;
+    pub mod verify;
 }
 mod updater;
 
 use crate::logging::{init_logger, LevelFilterArg};
-use crate::subcommands::{add, delete, init, update};
+use crate::prefetch::HashBackend;
+use crate::subcommands::{
+    add, delete, edit, export, import, init, list, migrate, show, update, verify,
+};
 use clap::{Parser, Subcommand};
 use std::process::ExitCode;
 
@@ -22,6 +47,9 @@ struct Cli {
     /// Logging level to print
     #[arg(long, value_enum, default_value_t = LevelFilterArg::Info)]
     log_level: LevelFilterArg,
+    /// How to fetch and hash artifacts
+    #[arg(long, value_enum, default_value_t = HashBackend::Native)]
+    hash_backend: HashBackend,
     #[command(subcommand)]
     command: Command,
 }
@@ -41,12 +69,34 @@ enum Command {
     Add(Box<add::AddArgs>),
     /// Update sources
     Update(update::UpdateArgs),
+    /// Check that the recorded hashes of sources still match their artifacts
+    ///
+    /// Exits with an error if any hash doesn't match; the source file is never modified.
+    Verify(verify::VerifyArgs),
+    /// Change options of an existing source
+    ///
+    /// If the change affects the artifact, its hash is refetched.
+    Edit(Box<edit::EditArgs>),
     /// Delete existing sources
     Delete {
         /// Name of sources to delete
         #[arg(required = true, value_name = "SOURCES")]
         source_names: Vec<String>,
     },
+    /// List sources
+    List(list::ListArgs),
+    /// Show all details of a source
+    Show(show::ShowArgs),
+    /// Import sources from niv, npins, yae or a flake.lock
+    Import(import::ImportArgs),
+    /// Export sources to another format
+    #[command(subcommand_value_name = "FORMAT")]
+    Export(export::ExportArgs),
+    /// Migrate the source file to the current schema version
+    ///
+    /// Older source files are migrated in memory whenever they are read,
+    /// and written back in the current schema by any command that modifies them.
+    Migrate(migrate::MigrateArgs),
 }
 
 fn main() -> ExitCode {
@@ -56,8 +106,15 @@ fn main() -> ExitCode {
 
     match cli.command {
         Command::Init => init::init(&cli.source_file),
-        Command::Add(args) => add::add(&cli.source_file, *args),
-        Command::Update(args) => update::update(&cli.source_file, args),
+        Command::Add(args) => add::add(&cli.source_file, cli.hash_backend, *args),
+        Command::Update(args) => update::update(&cli.source_file, cli.hash_backend, args),
+        Command::Verify(args) => verify::verify(&cli.source_file, cli.hash_backend, args),
+        Command::Edit(args) => edit::edit(&cli.source_file, cli.hash_backend, *args),
         Command::Delete { source_names } => delete::delete(&cli.source_file, source_names),
+        Command::List(args) => list::list(&cli.source_file, args),
+        Command::Show(args) => show::show(&cli.source_file, args),
+        Command::Import(args) => import::import(&cli.source_file, args),
+        Command::Export(args) => export::export(&cli.source_file, args),
+        Command::Migrate(args) => migrate::migrate(&cli.source_file, args),
     }
 }
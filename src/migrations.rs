@@ -0,0 +1,196 @@
+use serde_json::{Map, Value};
+
+/// The schema version of source files written by this version of nix-kunai.
+pub const CURRENT_SCHEMA_VERSION: u32 = 13;
+
+/// Key of the top-level field holding the schema version of a source file.
+/// Files without it predate schema versioning, and are considered version 0.
+pub const SCHEMA_VERSION_KEY: &str = "$version";
+
+type Migration = fn(&mut Map<String, Value>);
+
+/// Migrations between schema versions, where `MIGRATIONS[n]` migrates version `n` to `n + 1`.
+///
+/// Migrations work on raw json so that older files don't need to be representable
+/// by the current `Source` type.
+const MIGRATIONS: [Migration; CURRENT_SCHEMA_VERSION as usize] = [
+    // 0 -> 1: the schema version field was added, sources are unchanged
+    |_| {},
+    add_prerelease_policies,
+    // 2 -> 3: sources may have per-system artifacts, and no top-level hash if so;
+    // existing sources are unchanged, but older versions can't read such sources
+    |_| {},
+    // 3 -> 4: sources may have named artifacts, which older versions would drop
+    |_| {},
+    // 4 -> 5: sources may have a minimum age, which older versions would drop
+    |_| {},
+    // 5 -> 6: sources may have ignored versions, which older versions would drop
+    |_| {},
+    // 6 -> 7: sources may use the pypi update scheme, which older versions can't read
+    |_| {},
+    // 7 -> 8: sources may use the crates-io update scheme, which older versions can't read
+    |_| {},
+    // 8 -> 9: sources may use the npm update scheme, which older versions can't read,
+    // and record the integrity published by a registry, which they would drop
+    |_| {},
+    // 9 -> 10: sources may use the http-json update scheme, which older versions can't read
+    |_| {},
+    // 10 -> 11: sources may use the http-regex update scheme, which older versions can't read
+    |_| {},
+    // 11 -> 12: sources may use the feed update scheme, which older versions can't read
+    |_| {},
+    clear_branch_commits,
+];
+
+/// Iterates over the sources in the json of a source file.
+fn sources_mut(file: &mut Map<String, Value>) -> impl Iterator<Item = &mut Map<String, Value>> {
+    file.iter_mut()
+        .filter(|(name, _)| *name != SCHEMA_VERSION_KEY)
+        .filter_map(|(_, source)| source.as_object_mut())
+}
+
+/// 1 -> 2: tag-based schemes got a prerelease policy, which now excludes prereleases by default.
+/// Existing sources keep their previous behaviour, where git tags included prereleases
+/// and release schemes excluded them.
+fn add_prerelease_policies(file: &mut Map<String, Value>) {
+    for source in sources_mut(file) {
+        let Some(scheme) = source
+            .get_mut("update_scheme")
+            .and_then(Value::as_object_mut)
+        else {
+            continue;
+        };
+
+        let policy = match scheme.get("type").and_then(Value::as_str) {
+            Some("git-tags") => "include",
+            Some("github-releases" | "gitlab-releases" | "gitea-releases") => "exclude",
+            _ => continue,
+        };
+        scheme.insert("prereleases".to_string(), policy.into());
+    }
+}
+
+/// 12 -> 13: archives of git-branch sources are fetched at the recorded commit,
+/// which older versions recorded when a source was added but never updated,
+/// so it usually doesn't match the recorded hash. It is cleared, and recorded again by the next update.
+fn clear_branch_commits(file: &mut Map<String, Value>) {
+    for source in sources_mut(file) {
+        let is_branch = source
+            .get("update_scheme")
+            .and_then(|scheme| scheme.get("type"))
+            .and_then(Value::as_str)
+            == Some("git-branch");
+        if is_branch {
+            source.remove("rev");
+        }
+    }
+}
+
+/// Migrates the json of a source file from `from_version` to the current schema version.
+pub fn migrate(file: &mut Map<String, Value>, from_version: u32) {
+    for migration in &MIGRATIONS[from_version as usize..] {
+        migration(file);
+    }
+
+    file.insert(
+        SCHEMA_VERSION_KEY.to_string(),
+        CURRENT_SCHEMA_VERSION.into(),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::Source;
+    use serde_json::json;
+
+    /// A source as written by older versions, following an update scheme of type `scheme`.
+    fn source(scheme: &str) -> Value {
+        let update_scheme = match scheme {
+            "git-branch" => json!({
+                "type": scheme,
+                "repo_url": "https://github.com/owner/repo",
+                "branch": "main",
+                "short_hash_length": 6,
+            }),
+            _ => json!({
+                "type": scheme,
+                "unpack": true,
+                "repo_url": "https://github.com/owner/repo",
+                "tag_prefix": "v",
+            }),
+        };
+
+        json!({
+            "version": "1.2.0",
+            "hash": "sha256-47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=",
+            "latest_checked_version": "1.2.0",
+            "artifact_url_template": "https://github.com/owner/repo/archive/v{version}.tar.gz",
+            "pinned": false,
+            "update_scheme": update_scheme,
+        })
+    }
+
+    fn migrated(file: Value, from_version: u32) -> Value {
+        let Value::Object(mut file) = file else {
+            unreachable!()
+        };
+        migrate(&mut file, from_version);
+
+        Value::Object(file)
+    }
+
+    fn assert_readable(file: &Value, name: &str) {
+        if let Err(e) = serde_json::from_value::<Source>(file[name].clone()) {
+            panic!("migrated source {name} can't be read: {e}");
+        }
+    }
+
+    #[test]
+    fn unversioned_files_are_migrated() {
+        let file = migrated(json!({"tool": source("git-tags")}), 0);
+
+        assert_eq!(file[SCHEMA_VERSION_KEY], CURRENT_SCHEMA_VERSION);
+        assert_readable(&file, "tool");
+    }
+
+    #[test]
+    fn older_files_keep_their_prerelease_behaviour() {
+        for from_version in [0, 1] {
+            let file = json!({
+                "tags": source("git-tags"),
+                "releases": source("github-releases"),
+                "branch": source("git-branch"),
+            });
+
+            let file = migrated(file, from_version);
+            assert_eq!(file["tags"]["update_scheme"]["prereleases"], "include");
+            assert_eq!(file["releases"]["update_scheme"]["prereleases"], "exclude");
+            assert!(file["branch"]["update_scheme"].get("prereleases").is_none());
+            for name in ["tags", "releases", "branch"] {
+                assert_readable(&file, name);
+            }
+        }
+    }
+
+    #[test]
+    fn stale_branch_commits_are_cleared() {
+        let mut branch = source("git-branch");
+        branch["rev"] = "0123456789abcdef0123456789abcdef01234567".into();
+        let mut tags = source("git-tags");
+        tags["rev"] = "0123456789abcdef0123456789abcdef01234567".into();
+        let file = json!({SCHEMA_VERSION_KEY: 12, "branch": branch, "tags": tags});
+
+        let file = migrated(file, 12);
+        assert!(file["branch"].get("rev").is_none());
+        assert!(file["tags"].get("rev").is_some());
+        assert_readable(&file, "branch");
+    }
+
+    #[test]
+    fn current_files_are_unchanged() {
+        let file = json!({SCHEMA_VERSION_KEY: CURRENT_SCHEMA_VERSION, "tool": source("git-tags")});
+
+        assert_eq!(migrated(file.clone(), CURRENT_SCHEMA_VERSION), file);
+    }
+}
@@ -0,0 +1,162 @@
+use crate::source::{ArtifactKey, ArtifactUrl, BuildFullUrlError, SourceMap};
+use std::fs;
+use std::io;
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SourcesToNixError {
+    #[error("could not build URL for source {name}: {error}")]
+    BuildFullUrl {
+        name: String,
+        error: BuildFullUrlError,
+    },
+}
+
+#[derive(Debug, Error)]
+pub enum WriteNixFileError {
+    #[error(transparent)]
+    SourcesToNix(#[from] SourcesToNixError),
+    #[error("could not write nix file: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// Quotes a string as a nix string literal.
+fn nix_string(s: &str) -> String {
+    let escaped = s
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace("${", "\\${")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+        .replace('\t', "\\t");
+
+    format!("\"{escaped}\"")
+}
+
+/// Builds a nix file that maps every source to a call of the matching fetcher,
+/// `fetchzip` for unpacked sources and `fetchurl` otherwise.
+/// Sources with per-system artifacts get a fetcher call per system in `srcs`,
+/// with `src` picking the one for the host platform,
+/// and named artifacts are placed in `artifacts`.
+///
+/// The file is a function meant to be imported with `callPackage`.
+pub fn sources_to_nix(sources: &SourceMap) -> Result<String, SourcesToNixError> {
+    let needs_stdenv = sources.inner.values().any(|s| !s.systems.is_empty());
+    let mut nix = String::from(
+        "# This file is generated by nix-kunai; do not edit it by hand.\n\
+         {\n  fetchurl,\n  fetchzip,\n",
+    );
+    if needs_stdenv {
+        nix.push_str("  stdenv,\n");
+    }
+    nix.push_str("}:\n{\n");
+
+    for (name, source) in &sources.inner {
+        let urls = source.artifact_urls(&source.version).map_err(|error| {
+            SourcesToNixError::BuildFullUrl {
+                name: name.clone(),
+                error,
+            }
+        })?;
+        if source.systems.is_empty() {
+            nix.push_str(&format!("  {} = {{\n", nix_string(name)));
+        } else {
+            nix.push_str(&format!("  {} = rec {{\n", nix_string(name)));
+        }
+        nix.push_str(&format!("    version = {};\n", nix_string(&source.version)));
+        if let Some(rev) = &source.rev {
+            nix.push_str(&format!("    rev = {};\n", nix_string(rev)));
+        }
+
+        let extension = source.update_scheme.archive_extension();
+        let mut srcs = String::new();
+        let mut artifacts = String::new();
+        for artifact in &urls {
+            let hash = source.hash_of(&artifact.key).unwrap_or_default();
+            match &artifact.key {
+                ArtifactKey::Main => {
+                    nix.push_str(&fetcher_call("src", artifact, hash, extension, "    "));
+                }
+                ArtifactKey::System(system) => {
+                    srcs.push_str(&fetcher_call(
+                        &nix_string(system),
+                        artifact,
+                        hash,
+                        extension,
+                        "      ",
+                    ));
+                }
+                // Optional artifacts that couldn't be fetched have no hash
+                ArtifactKey::Named(name) if hash.is_empty() => {
+                    artifacts.push_str(&format!("      {} = null;\n", nix_string(name)));
+                }
+                ArtifactKey::Named(name) => {
+                    artifacts.push_str(&fetcher_call(
+                        &nix_string(name),
+                        artifact,
+                        hash,
+                        None,
+                        "      ",
+                    ));
+                }
+            }
+        }
+
+        if !srcs.is_empty() {
+            nix.push_str(&format!("    srcs = {{\n{srcs}    }};\n"));
+            nix.push_str(&format!(
+                "    src = srcs.${{stdenv.hostPlatform.system}} or (throw {});\n",
+                nix_string(&format!("{name} is not available for this system"))
+            ));
+        }
+        if !artifacts.is_empty() {
+            nix.push_str(&format!("    artifacts = {{\n{artifacts}    }};\n"));
+        }
+        nix.push_str("  };\n");
+    }
+
+    nix.push_str("}\n");
+
+    Ok(nix)
+}
+
+/// Builds the binding of the attribute `attr` to a fetcher call for an artifact,
+/// `fetchzip` for unpacked artifacts and `fetchurl` otherwise.
+/// `extension` tells `fetchzip` the archive type if the URL doesn't.
+fn fetcher_call(
+    attr: &str,
+    artifact: &ArtifactUrl,
+    hash: &str,
+    extension: Option<&str>,
+    indent: &str,
+) -> String {
+    let fetcher = if artifact.unpack {
+        "fetchzip"
+    } else {
+        "fetchurl"
+    };
+    let extension = match extension.filter(|_| artifact.unpack) {
+        Some(extension) => format!("{indent}  extension = {};\n", nix_string(extension)),
+        None => String::new(),
+    };
+
+    format!(
+        "{indent}{attr} = {fetcher} {{\n\
+         {indent}  url = {};\n\
+         {extension}\
+         {indent}  hash = {};\n\
+         {indent}}};\n",
+        nix_string(artifact.url.as_str()),
+        nix_string(hash)
+    )
+}
+
+pub fn write_nix_file<P: AsRef<Path>>(
+    sources: &SourceMap,
+    path: P,
+) -> Result<(), WriteNixFileError> {
+    fs::write(path, sources_to_nix(sources)?)?;
+
+    Ok(())
+}
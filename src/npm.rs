@@ -0,0 +1,170 @@
+use crate::http::{get_reader, HttpGetError};
+use crate::releases::LatestRelease;
+use crate::tags::{parse_version, VersionGlob};
+use jiff::Timestamp;
+use semver::VersionReq;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use thiserror::Error;
+use url::Url;
+
+pub const DEFAULT_NPM_REGISTRY_URL: &str = "https://registry.npmjs.org";
+
+/// The dist-tag followed when neither a dist-tag nor a version constraint is set.
+pub const DEFAULT_DIST_TAG: &str = "latest";
+
+/// The document describing a package and all of its versions.
+#[derive(Deserialize)]
+struct Packument {
+    #[serde(rename = "dist-tags", default)]
+    dist_tags: BTreeMap<String, String>,
+    #[serde(default)]
+    versions: BTreeMap<String, PackageVersion>,
+    /// Publish dates by version, along with the `created` and `modified` dates of the package
+    #[serde(default)]
+    time: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct PackageVersion {
+    dist: Dist,
+}
+
+#[derive(Deserialize)]
+struct Dist {
+    tarball: String,
+    /// Hashes of the tarball in SRI format, missing for very old versions
+    integrity: Option<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum FetchLatestNpmVersionError {
+    #[error("could not build package URL: {0}")]
+    InvalidPackageUrl(#[from] url::ParseError),
+    #[error("failed to fetch package: {0}")]
+    Http(#[from] HttpGetError),
+    #[error("package document is malformed: {0}")]
+    MalformedPackage(#[from] serde_json::Error),
+    #[error("the package has no dist-tag {0}")]
+    DistTagNotFound(String),
+    #[error("dist-tag {tag} points to version {version}, which is ignored")]
+    DistTagIgnored { tag: String, version: String },
+    #[error("no version matches the constraint")]
+    NoMatchingVersion,
+    #[error("the package has no version {0}")]
+    VersionNotFound(String),
+    #[error("tarball URL {0} does not end with the version of the package")]
+    VersionNotInTarballUrl(String),
+}
+
+/// Which version of a package is followed.
+pub enum NpmVersionSelector<'a> {
+    /// The version a dist-tag such as `latest` or `next` points to
+    DistTag(&'a str),
+    /// The newest version matching a semver range
+    Constraint(&'a VersionReq),
+    /// A specific version, whether it is ignored or not
+    Exact(&'a str),
+}
+
+/// The newest version of a package, along with the URL of its tarball.
+pub struct NpmRelease {
+    pub release: LatestRelease,
+    pub tarball: String,
+}
+
+fn fetch_packument(
+    registry_url: Option<&Url>,
+    package: &str,
+) -> Result<Packument, FetchLatestNpmVersionError> {
+    let base = match registry_url {
+        Some(url) => url.as_str().trim_end_matches('/').to_string(),
+        None => DEFAULT_NPM_REGISTRY_URL.to_string(),
+    };
+    // The slash of scoped packages (e.g. `@types/node`) is part of the name, not the path
+    let url = Url::parse(&format!("{base}/{}", package.replace('/', "%2F")))?;
+
+    // Documents of packages with many versions can be larger than `get_json` allows
+    let reader = get_reader(&url)?;
+
+    Ok(serde_json::from_reader(reader)?)
+}
+
+/// Gets the version of a package that `selector` picks from the registry,
+/// along with the URL and integrity of its tarball.
+/// Versions matching any of `ignored` are skipped when following a constraint,
+/// and are an error when a dist-tag points to one.
+///
+/// `registry_url` is the base URL of an npm-compatible registry, defaulting to the npm registry.
+pub fn fetch_latest_npm_version(
+    registry_url: Option<&Url>,
+    package: &str,
+    selector: &NpmVersionSelector,
+    ignored: &[VersionGlob],
+) -> Result<NpmRelease, FetchLatestNpmVersionError> {
+    let packument = fetch_packument(registry_url, package)?;
+    let is_ignored = |version: &str| ignored.iter().any(|glob| glob.matches(version));
+
+    let version = match selector {
+        NpmVersionSelector::DistTag(tag) => {
+            let version = packument
+                .dist_tags
+                .get(*tag)
+                .ok_or_else(|| FetchLatestNpmVersionError::DistTagNotFound(tag.to_string()))?;
+            // A dist-tag points to a single version, so there is no next best one to fall back to
+            if is_ignored(version) {
+                return Err(FetchLatestNpmVersionError::DistTagIgnored {
+                    tag: tag.to_string(),
+                    version: version.clone(),
+                });
+            }
+
+            version.clone()
+        }
+        NpmVersionSelector::Constraint(constraint) => packument
+            .versions
+            .keys()
+            .filter(|version| !is_ignored(version))
+            .filter_map(|version| Some((parse_version(version)?, version)))
+            .filter(|(parsed, _)| constraint.matches(parsed))
+            .max_by(|(a, _), (b, _)| a.cmp(b))
+            .map(|(_, version)| version.clone())
+            .ok_or(FetchLatestNpmVersionError::NoMatchingVersion)?,
+        NpmVersionSelector::Exact(version) => version.to_string(),
+    };
+
+    let dist = &packument
+        .versions
+        .get(&version)
+        .ok_or_else(|| FetchLatestNpmVersionError::VersionNotFound(version.clone()))?
+        .dist;
+    let published_at = packument
+        .time
+        .get(&version)
+        .and_then(|time| time.parse::<Timestamp>().ok());
+
+    Ok(NpmRelease {
+        tarball: dist.tarball.clone(),
+        release: LatestRelease {
+            version,
+            published_at,
+            integrity: dist.integrity.clone(),
+            artifact_url: None,
+            rev: None,
+        },
+    })
+}
+
+/// Turns the tarball URL of a version into an artifact URL template,
+/// e.g. `https://registry.npmjs.org/left-pad/-/left-pad-{version}.tgz`.
+pub fn tarball_url_template(
+    tarball: &str,
+    version: &str,
+) -> Result<String, FetchLatestNpmVersionError> {
+    let suffix = format!("-{version}.tgz");
+
+    tarball
+        .strip_suffix(&suffix)
+        .map(|base| format!("{base}-{{version}}.tgz"))
+        .ok_or_else(|| FetchLatestNpmVersionError::VersionNotInTarballUrl(tarball.to_string()))
+}
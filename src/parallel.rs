@@ -0,0 +1,67 @@
+use std::collections::BTreeMap;
+use std::num::NonZeroUsize;
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+/// Maps `f` over `items` using up to `jobs` threads,
+/// passing each result to `consume` in the same order as `items`.
+///
+/// Results are consumed as soon as every result before them is available,
+/// so with a single job this behaves just like a sequential loop.
+/// If `consume` breaks, no new items are started and the break is returned.
+pub fn map_ordered<T, R>(
+    items: &[T],
+    jobs: NonZeroUsize,
+    f: impl Fn(&T) -> R + Sync,
+    mut consume: impl FnMut(R) -> ControlFlow<()>,
+) -> ControlFlow<()>
+where
+    T: Sync,
+    R: Send,
+{
+    let next_item = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+
+    thread::scope(|scope| {
+        let (sender, receiver) = mpsc::channel();
+
+        for _ in 0..jobs.get().min(items.len()) {
+            let sender = sender.clone();
+            let (next_item, stop, f) = (&next_item, &stop, &f);
+
+            scope.spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    let index = next_item.fetch_add(1, Ordering::Relaxed);
+                    let Some(item) = items.get(index) else {
+                        break;
+                    };
+
+                    if sender.send((index, f(item))).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        let mut pending = BTreeMap::new();
+        let mut next_result = 0;
+
+        for (index, result) in receiver {
+            pending.insert(index, result);
+
+            while let Some(result) = pending.remove(&next_result) {
+                next_result += 1;
+
+                if consume(result).is_break() {
+                    stop.store(true, Ordering::Relaxed);
+                    return ControlFlow::Break(());
+                }
+            }
+        }
+
+        ControlFlow::Continue(())
+    })
+}
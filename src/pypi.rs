@@ -0,0 +1,323 @@
+use crate::http::{get_json, HttpGetError};
+use crate::releases::LatestRelease;
+use crate::tags::{PrereleasePolicy, TagFilter};
+use jiff::Timestamp;
+use regex::Regex;
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+use thiserror::Error;
+use url::Url;
+
+pub const DEFAULT_PYPI_URL: &str = "https://pypi.org";
+
+/// Host serving the files of packages on PyPI, including stable redirects
+/// that don't depend on the hash of a file (unlike the URLs returned by the API).
+const PYPI_FILES_URL: &str = "https://files.pythonhosted.org/packages";
+
+#[derive(Deserialize)]
+struct PypiProject {
+    releases: BTreeMap<String, Vec<PypiFile>>,
+}
+
+#[derive(Deserialize)]
+struct PypiFile {
+    filename: String,
+    packagetype: String,
+    #[serde(default)]
+    yanked: bool,
+    upload_time_iso_8601: Option<Timestamp>,
+}
+
+#[derive(Debug, Error)]
+pub enum FetchLatestPypiReleaseError {
+    #[error("could not build PyPI API URL: {0}")]
+    InvalidApiUrl(#[from] url::ParseError),
+    #[error("failed to fetch package: {0}")]
+    Http(#[from] HttpGetError),
+    #[error("no release fits the filter and has a matching file")]
+    NoMatchingRelease,
+    #[error("file name {0} does not contain the version of its release")]
+    VersionNotInFileName(String),
+}
+
+/// Which file of a release is used as the artifact when the artifact URL is inferred.
+pub enum PypiFileKind<'a> {
+    Sdist,
+    /// A wheel whose file name ends with these compatibility tags, e.g. `py3-none-any`
+    Wheel(&'a str),
+}
+
+impl PypiFileKind<'_> {
+    fn matches(&self, file: &PypiFile) -> bool {
+        match self {
+            Self::Sdist => file.packagetype == "sdist",
+            Self::Wheel(tags) => {
+                file.packagetype == "bdist_wheel"
+                    && file.filename.ends_with(&format!("-{tags}.whl"))
+            }
+        }
+    }
+}
+
+/// A version following PEP 440, e.g. `1.2.0`, `2.0rc1` or `1.0.post1.dev2`,
+/// without support for epochs and local versions, which are rare on PyPI.
+#[derive(PartialEq, Eq)]
+struct PythonVersion {
+    /// Release numbers, without trailing zeros so that `1.0` equals `1.0.0`
+    release: Vec<u64>,
+    /// Rank and number of the prerelease: dev releases without one come first,
+    /// then alpha, beta and release candidates, then final releases
+    pre: (u8, u64),
+    post: Option<u64>,
+    /// Dev releases come before the release they precede
+    dev: Option<u64>,
+    is_prerelease: bool,
+}
+
+static PYTHON_VERSION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?ix)^v?
+        (?<release>[0-9]+(?:\.[0-9]+)*)
+        (?:[-_.]?(?<pre_label>a|alpha|b|beta|c|rc|pre|preview)[-_.]?(?<pre>[0-9]*))?
+        (?:[-_.]?(?:post|rev|r)[-_.]?(?<post>[0-9]*))?
+        (?:[-_.]?dev[-_.]?(?<dev>[0-9]*))?
+        $",
+    )
+    .expect("python version regex is valid")
+});
+
+impl PythonVersion {
+    fn parse(version: &str) -> Option<Self> {
+        let captures = PYTHON_VERSION.captures(version)?;
+        let number = |name| captures.name(name).map(|n| n.as_str().parse().unwrap_or(0));
+
+        let mut release = captures["release"]
+            .split('.')
+            .map(str::parse)
+            .collect::<Result<Vec<u64>, _>>()
+            .ok()?;
+        while release.last() == Some(&0) {
+            release.pop();
+        }
+
+        let dev = number("dev");
+        let pre_rank = match captures
+            .name("pre_label")
+            .map(|l| l.as_str().to_lowercase())
+        {
+            Some(label) if label.starts_with('a') => 1,
+            Some(label) if label.starts_with('b') => 2,
+            Some(_) => 3,
+            None if dev.is_some() && captures.name("post").is_none() => 0,
+            None => 4,
+        };
+
+        Some(Self {
+            release,
+            pre: (pre_rank, number("pre").unwrap_or(0)),
+            post: number("post"),
+            dev,
+            is_prerelease: pre_rank < 4 || dev.is_some(),
+        })
+    }
+}
+
+impl Ord for PythonVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.release
+            .cmp(&other.release)
+            .then(self.pre.cmp(&other.pre))
+            .then(self.post.cmp(&other.post))
+            .then_with(|| match (self.dev, other.dev) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(a), Some(b)) => a.cmp(&b),
+            })
+    }
+}
+
+impl PartialOrd for PythonVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn fetch_project(
+    registry_url: Option<&Url>,
+    package: &str,
+) -> Result<PypiProject, FetchLatestPypiReleaseError> {
+    let base = match registry_url {
+        Some(url) => url.as_str().trim_end_matches('/').to_string(),
+        None => DEFAULT_PYPI_URL.to_string(),
+    };
+    let url = Url::parse(&format!("{base}/pypi/{package}/json"))?;
+
+    Ok(get_json(&url, &[("Accept", "application/json")])?)
+}
+
+/// Finds the newest release that passes `filter` and has a non-yanked file
+/// for which `accept` returns a value, along with that value.
+fn find_latest<'a, T>(
+    project: &'a PypiProject,
+    filter: &TagFilter,
+    accept: impl Fn(&str, &[&'a PypiFile]) -> Option<T>,
+) -> Option<(LatestRelease, T)> {
+    project
+        .releases
+        .iter()
+        .filter_map(|(version, files)| {
+            let parsed = PythonVersion::parse(version)?;
+            let version = filter.version_from_release_tag(version, parsed.is_prerelease)?;
+            let files = files.iter().filter(|f| !f.yanked).collect::<Vec<_>>();
+            let accepted = accept(&version, &files)?;
+            let published_at = files.iter().filter_map(|f| f.upload_time_iso_8601).min();
+
+            Some((
+                parsed,
+                LatestRelease {
+                    version,
+                    published_at,
+                    integrity: None,
+                    artifact_url: None,
+                    rev: None,
+                },
+                accepted,
+            ))
+        })
+        .max_by(|(a, ..), (b, ..)| a.cmp(b))
+        .map(|(_, release, accepted)| (release, accepted))
+}
+
+/// Gets the newest release of a PyPI package that has all files named in `file_names_for(version)`,
+/// ignoring yanked files.
+///
+/// `registry_url` is the base URL of a PyPI-compatible JSON API, defaulting to PyPI itself.
+pub fn fetch_latest_pypi_release(
+    registry_url: Option<&Url>,
+    package: &str,
+    filter: &TagFilter,
+    file_names_for: impl Fn(&str) -> Option<Vec<String>>,
+) -> Result<LatestRelease, FetchLatestPypiReleaseError> {
+    let project = fetch_project(registry_url, package)?;
+
+    find_latest(&project, filter, |version, files| {
+        file_names_for(version)?
+            .iter()
+            .all(|name| files.iter().any(|f| &f.filename == name))
+            .then_some(())
+    })
+    .map(|(release, _)| release)
+    .ok_or(FetchLatestPypiReleaseError::NoMatchingRelease)
+}
+
+/// Infers an artifact URL template for a PyPI package from a file of the given kind,
+/// using the newest release that passes `filter`, or `version` if set.
+/// Returns the version of that release along with the template.
+///
+/// The template uses the stable `files.pythonhosted.org` URLs of the file,
+/// so the package is always looked up on PyPI itself:
+/// other registries serve files from URLs that can't be derived from the version.
+pub fn infer_pypi_artifact_url(
+    package: &str,
+    filter: &TagFilter,
+    version: Option<&str>,
+    kind: &PypiFileKind,
+) -> Result<(String, String), FetchLatestPypiReleaseError> {
+    let project = fetch_project(None, package)?;
+    // A requested version is used whatever the filter would say about it
+    let any_version = TagFilter {
+        prereleases: PrereleasePolicy::Include,
+        ..Default::default()
+    };
+    let filter = if version.is_some() {
+        &any_version
+    } else {
+        filter
+    };
+
+    let (release, filename) = find_latest(&project, filter, |v, files| {
+        if version.is_some_and(|version| version != v) {
+            return None;
+        }
+        files
+            .iter()
+            .find(|f| kind.matches(f))
+            .map(|f| f.filename.clone())
+    })
+    .ok_or(FetchLatestPypiReleaseError::NoMatchingRelease)?;
+
+    let template_filename = filename.replacen(&format!("-{}", release.version), "-{version}", 1);
+    // File names may spell the version differently than the release does
+    if template_filename == filename {
+        return Err(FetchLatestPypiReleaseError::VersionNotInFileName(filename));
+    }
+    let directory = match kind {
+        PypiFileKind::Sdist => "source",
+        // Wheels are stored by their python tag, the third to last part of their name
+        PypiFileKind::Wheel(_) => filename
+            .trim_end_matches(".whl")
+            .rsplit('-')
+            .nth(2)
+            .unwrap_or("py3"),
+    };
+    let first_letter = package.chars().next().unwrap_or('_');
+
+    Ok((
+        release.version,
+        format!("{PYPI_FILES_URL}/{directory}/{first_letter}/{package}/{template_filename}"),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(version: &str) -> PythonVersion {
+        PythonVersion::parse(version).unwrap()
+    }
+
+    #[test]
+    fn versions_follow_pep_440_order() {
+        let ordered = [
+            "1.0.dev1",
+            "1.0a1",
+            "1.0a2.dev1",
+            "1.0a2",
+            "1.0b1",
+            "1.0rc1",
+            "1.0",
+            "1.0.post1.dev1",
+            "1.0.post1",
+            "1.1",
+            "1.10",
+        ];
+
+        for pair in ordered.windows(2) {
+            assert!(
+                version(pair[0]) < version(pair[1]),
+                "{} < {}",
+                pair[0],
+                pair[1]
+            );
+        }
+    }
+
+    #[test]
+    fn trailing_zeros_and_spellings_are_equal() {
+        assert!(version("1.0") == version("1.0.0"));
+        assert!(version("1.0rc1") == version("1.0-RC.1"));
+        assert!(version("1.0a1") == version("1.0alpha1"));
+    }
+
+    #[test]
+    fn prereleases_are_detected() {
+        assert!(version("2.0b1").is_prerelease);
+        assert!(version("2.0.dev3").is_prerelease);
+        assert!(version("2.0.post1.dev1").is_prerelease);
+        assert!(!version("2.0.post1").is_prerelease);
+        assert!(PythonVersion::parse("2.0+local").is_none());
+    }
+}
@@ -0,0 +1,420 @@
+use crate::http::{get_json_page, HttpGetError};
+use crate::tags::TagFilter;
+use clap::ValueEnum;
+use jiff::Timestamp;
+use serde::Deserialize;
+use std::env;
+use thiserror::Error;
+use url::Url;
+
+pub const DEFAULT_GITHUB_API_URL: &str = "https://api.github.com";
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum GitProvider {
+    Github,
+    Gitlab,
+    /// Gitea or Forgejo
+    Gitea,
+}
+
+impl GitProvider {
+    /// Tells the provider from the host of a repository URL, which only works for
+    /// github.com and gitlab.com (and their subdomains); any other host, such as a Gitea/Forgejo
+    /// or self-hosted GitLab instance, has to be named explicitly.
+    pub fn detect(repository: &Url) -> Option<Self> {
+        let host = repository.host_str()?;
+        let is_on = |domain: &str| {
+            host == domain
+                || host
+                    .strip_suffix(domain)
+                    .is_some_and(|subdomain| subdomain.ends_with('.'))
+        };
+
+        if is_on("github.com") {
+            Some(Self::Github)
+        } else if is_on("gitlab.com") {
+            Some(Self::Gitlab)
+        } else {
+            None
+        }
+    }
+
+    /// Builds the URL of a source archive of `repository` at `git_ref`,
+    /// which may contain placeholders such as `{branch}`.
+    pub fn archive_url_template(self, repository: &Url, git_ref: &str) -> Option<String> {
+        let repository_str = repository.as_str().trim_end_matches(".git");
+
+        match self {
+            Self::Github | Self::Gitea => {
+                Some(format!("{repository_str}/archive/{git_ref}.tar.gz"))
+            }
+
+            Self::Gitlab => {
+                let repo_name = repository
+                    .path_segments()
+                    .and_then(|mut iter| iter.next_back())
+                    .map(|name| name.trim_end_matches(".git"))?;
+
+                Some(format!(
+                    "{repository_str}/-/archive/{git_ref}/{repo_name}-{git_ref}.tar.gz"
+                ))
+            }
+        }
+    }
+
+    /// The URL of the feed of `repository`'s releases (or tags, for GitLab),
+    /// which unlike the releases API isn't rate limited.
+    pub fn releases_feed_url(self, repository: &Url) -> Result<Url, url::ParseError> {
+        let repository_str = repository
+            .as_str()
+            .trim_end_matches('/')
+            .trim_end_matches(".git");
+
+        Url::parse(&match self {
+            Self::Github => format!("{repository_str}/releases.atom"),
+            Self::Gitlab => format!("{repository_str}/-/tags?format=atom"),
+            Self::Gitea => format!("{repository_str}/releases.rss"),
+        })
+    }
+}
+
+/// A release, normalized across providers.
+struct Release {
+    tag_name: String,
+    prerelease: bool,
+    published_at: Option<Timestamp>,
+    /// File names of the release's assets
+    asset_names: Vec<String>,
+}
+
+/// The newest release that fits, as returned by `fetch_latest_release`
+/// and by the functions following package registries.
+pub struct LatestRelease {
+    pub version: String,
+    pub published_at: Option<Timestamp>,
+    /// Hash of the release's artifact in SRI format, as published by its registry
+    pub integrity: Option<String>,
+    /// URL of the release's artifact, for schemes where it is reported with the version
+    /// instead of following the artifact URL template
+    pub artifact_url: Option<String>,
+    /// Full hash of the commit the version was built from, for schemes following a branch
+    pub rev: Option<String>,
+}
+
+/// Most pages of releases fetched before giving up on finding one that fits,
+/// to bound the number of requests made for repositories with no matching release.
+const MAX_RELEASE_PAGES: usize = 10;
+
+/// A page of releases, along with the URL of the next page, if any.
+type ReleasePage = (Vec<Release>, Option<Url>);
+
+#[derive(Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    draft: bool,
+    prerelease: bool,
+    published_at: Option<Timestamp>,
+    assets: Vec<GithubAsset>,
+}
+
+#[derive(Deserialize)]
+struct GithubAsset {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct GitlabRelease {
+    tag_name: String,
+    #[serde(default)]
+    upcoming_release: bool,
+    released_at: Option<Timestamp>,
+    assets: GitlabAssets,
+}
+
+#[derive(Deserialize)]
+struct GitlabAssets {
+    links: Vec<GitlabAssetLink>,
+}
+
+#[derive(Deserialize)]
+struct GitlabAssetLink {
+    name: String,
+    url: Url,
+    direct_asset_url: Option<Url>,
+}
+
+#[derive(Debug, Error)]
+pub enum FetchLatestReleaseError {
+    #[error("could not get owner and repository name from {0}")]
+    InvalidRepoUrl(Url),
+    #[error("could not build releases API URL: {0}")]
+    InvalidApiUrl(#[from] url::ParseError),
+    #[error("failed to fetch releases: {0}")]
+    Http(#[from] HttpGetError),
+    #[error("no release fits the tag prefix and has a matching artifact")]
+    NoMatchingRelease,
+}
+
+fn last_path_segment(url: &Url) -> Option<String> {
+    url.path_segments()?
+        .next_back()
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+fn repo_path(repo_url: &Url) -> Result<Vec<&str>, FetchLatestReleaseError> {
+    let path = repo_url
+        .path_segments()
+        .ok_or_else(|| FetchLatestReleaseError::InvalidRepoUrl(repo_url.clone()))?
+        .filter(|s| !s.is_empty())
+        .map(|s| s.trim_end_matches(".git"))
+        .collect::<Vec<_>>();
+
+    if path.len() < 2 {
+        return Err(FetchLatestReleaseError::InvalidRepoUrl(repo_url.clone()));
+    }
+
+    Ok(path)
+}
+
+/// The default API base URL for a self-hosted instance,
+/// which lives on the same host as the repository.
+fn api_url_on_host(repo_url: &Url, api_path: &str) -> Url {
+    let mut api_url = repo_url.clone();
+    api_url.set_path(api_path);
+    api_url.set_query(None);
+
+    api_url
+}
+
+fn join_api_url(api_url: &Url, path: &str) -> Result<Url, FetchLatestReleaseError> {
+    Ok(Url::parse(&format!(
+        "{}/{path}",
+        api_url.as_str().trim_end_matches('/')
+    ))?)
+}
+
+fn github_releases_url(
+    api_url: Option<&Url>,
+    repo_url: &Url,
+) -> Result<Url, FetchLatestReleaseError> {
+    let repo_path = repo_path(repo_url)?;
+    let api_url = match api_url {
+        Some(url) => url.clone(),
+        None => Url::parse(DEFAULT_GITHUB_API_URL).expect("default API URL is valid"),
+    };
+
+    join_api_url(
+        &api_url,
+        &format!(
+            "repos/{}/{}/releases?per_page=100",
+            repo_path[0], repo_path[1]
+        ),
+    )
+}
+
+fn fetch_github_releases(page_url: &Url) -> Result<ReleasePage, FetchLatestReleaseError> {
+    let token = env::var("GITHUB_TOKEN").ok().map(|t| format!("Bearer {t}"));
+    let mut headers = vec![("Accept", "application/vnd.github+json")];
+    if let Some(token) = &token {
+        headers.push(("Authorization", token));
+    }
+
+    let (releases, next): (Vec<GithubRelease>, _) = get_json_page(page_url, &headers)?;
+
+    Ok((
+        releases
+            .into_iter()
+            .filter(|release| !release.draft)
+            .map(|release| Release {
+                tag_name: release.tag_name,
+                prerelease: release.prerelease,
+                published_at: release.published_at,
+                asset_names: release.assets.into_iter().map(|a| a.name).collect(),
+            })
+            .collect(),
+        next,
+    ))
+}
+
+fn gitlab_releases_url(
+    api_url: Option<&Url>,
+    repo_url: &Url,
+) -> Result<Url, FetchLatestReleaseError> {
+    // GitLab accepts the URL-encoded full path of a project in place of its ID,
+    // which also takes care of projects in subgroups
+    let project_id = repo_path(repo_url)?.join("%2F");
+    let api_url = match api_url {
+        Some(url) => url.clone(),
+        None => api_url_on_host(repo_url, "/api/v4"),
+    };
+
+    join_api_url(
+        &api_url,
+        &format!("projects/{project_id}/releases?per_page=100"),
+    )
+}
+
+fn fetch_gitlab_releases(page_url: &Url) -> Result<ReleasePage, FetchLatestReleaseError> {
+    let token = env::var("GITLAB_TOKEN").ok();
+    let mut headers = vec![];
+    if let Some(token) = &token {
+        headers.push(("PRIVATE-TOKEN", token.as_str()));
+    }
+
+    let (releases, next): (Vec<GitlabRelease>, _) = get_json_page(page_url, &headers)?;
+
+    Ok((
+        releases
+            .into_iter()
+            .filter(|release| !release.upcoming_release)
+            .map(|release| Release {
+                tag_name: release.tag_name,
+                // GitLab has no concept of prereleases
+                prerelease: false,
+                published_at: release.released_at,
+                asset_names: release
+                    .assets
+                    .links
+                    .into_iter()
+                    .flat_map(|link| {
+                        [
+                            Some(link.name),
+                            last_path_segment(&link.url),
+                            link.direct_asset_url.as_ref().and_then(last_path_segment),
+                        ]
+                    })
+                    .flatten()
+                    .collect(),
+            })
+            .collect(),
+        next,
+    ))
+}
+
+fn gitea_releases_url(
+    api_url: Option<&Url>,
+    repo_url: &Url,
+) -> Result<Url, FetchLatestReleaseError> {
+    let repo_path = repo_path(repo_url)?;
+    let api_url = match api_url {
+        Some(url) => url.clone(),
+        None => api_url_on_host(repo_url, "/api/v1"),
+    };
+
+    join_api_url(
+        &api_url,
+        &format!("repos/{}/{}/releases?limit=50", repo_path[0], repo_path[1]),
+    )
+}
+
+fn fetch_gitea_releases(page_url: &Url) -> Result<ReleasePage, FetchLatestReleaseError> {
+    let token = env::var("GITEA_TOKEN").ok().map(|t| format!("token {t}"));
+    let mut headers = vec![];
+    if let Some(token) = &token {
+        headers.push(("Authorization", token.as_str()));
+    }
+
+    // Gitea's release and asset objects are compatible with GitHub's
+    let (releases, next): (Vec<GithubRelease>, _) = get_json_page(page_url, &headers)?;
+
+    Ok((
+        releases
+            .into_iter()
+            .filter(|release| !release.draft)
+            .map(|release| Release {
+                tag_name: release.tag_name,
+                prerelease: release.prerelease,
+                published_at: release.published_at,
+                asset_names: release.assets.into_iter().map(|a| a.name).collect(),
+            })
+            .collect(),
+        next,
+    ))
+}
+
+/// Gets the version of the newest published release in a repository
+/// that has all assets named in `asset_names_for(version)`.
+///
+/// If `api_url` is not set, GitHub's public API is used for GitHub,
+/// and the API of the repository's own host is used otherwise.
+/// Drafts are never considered, and prereleases (whether flagged by the host or detected from the tag)
+/// only according to the prerelease policy of `filter`.
+/// Further pages of releases are fetched until one fits, up to `MAX_RELEASE_PAGES`.
+pub fn fetch_latest_release(
+    provider: GitProvider,
+    api_url: Option<&Url>,
+    repo_url: &Url,
+    filter: &TagFilter,
+    asset_names_for: impl Fn(&str) -> Option<Vec<String>>,
+) -> Result<LatestRelease, FetchLatestReleaseError> {
+    let mut page_url = Some(match provider {
+        GitProvider::Github => github_releases_url(api_url, repo_url)?,
+        GitProvider::Gitlab => gitlab_releases_url(api_url, repo_url)?,
+        GitProvider::Gitea => gitea_releases_url(api_url, repo_url)?,
+    });
+
+    // Releases are listed newest first, so further pages are only needed until one fits
+    for _ in 0..MAX_RELEASE_PAGES {
+        let Some(url) = page_url.take() else {
+            break;
+        };
+        let (releases, next) = match provider {
+            GitProvider::Github => fetch_github_releases(&url)?,
+            GitProvider::Gitlab => fetch_gitlab_releases(&url)?,
+            GitProvider::Gitea => fetch_gitea_releases(&url)?,
+        };
+
+        let latest = releases.iter().find_map(|release| {
+            let version = filter.version_from_release_tag(&release.tag_name, release.prerelease)?;
+            let asset_names = asset_names_for(&version)?;
+
+            asset_names
+                .iter()
+                .all(|name| release.asset_names.contains(name))
+                .then_some(LatestRelease {
+                    version,
+                    published_at: release.published_at,
+                    integrity: None,
+                    artifact_url: None,
+                    rev: None,
+                })
+        });
+        if let Some(latest) = latest {
+            return Ok(latest);
+        }
+
+        page_url = next;
+    }
+
+    Err(FetchLatestReleaseError::NoMatchingRelease)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect(repository: &str) -> Option<GitProvider> {
+        GitProvider::detect(&Url::parse(repository).unwrap())
+    }
+
+    #[test]
+    fn providers_are_only_detected_on_their_own_hosts() {
+        assert!(matches!(
+            detect("https://github.com/owner/repo"),
+            Some(GitProvider::Github)
+        ));
+        assert!(matches!(
+            detect("https://www.github.com/owner/repo"),
+            Some(GitProvider::Github)
+        ));
+        assert!(matches!(
+            detect("https://gitlab.com/group/sub/repo.git"),
+            Some(GitProvider::Gitlab)
+        ));
+        assert!(detect("https://notgithub.com/owner/repo").is_none());
+        assert!(detect("https://github.com.example.org/owner/repo").is_none());
+        assert!(detect("https://codeberg.org/owner/repo").is_none());
+        assert!(detect("https://git.sr.ht/~owner/repo").is_none());
+    }
+}
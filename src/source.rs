@@ -1,26 +1,149 @@
+use crate::age::MinimumAge;
+use crate::http::HttpGetError;
+use crate::migrations::{migrate, CURRENT_SCHEMA_VERSION, SCHEMA_VERSION_KEY};
+use crate::prefetch::{native_artifact_hash, HashBackend, UnpackError};
+use crate::tags::VersionGlob;
 use crate::updater::VersionUpdateScheme;
 use serde::{Deserialize, Serialize};
 use serde_json::error::Category as JsonErrorCategory;
 use std::collections::BTreeMap;
-use std::fs::File;
-use std::io::{self, Read, Write};
-use std::path::Path;
+use std::fmt;
+use std::fs::{self, File, TryLockError};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use thiserror::Error;
 use url::Url;
 
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct Source {
     pub version: String,
+    /// Hash of the artifact; empty for sources with per-system artifacts
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub hash: String,
+    /// Integrity of the artifact in SRI format, as published by its registry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub integrity: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub rev: Option<String>,
     pub latest_checked_version: String,
     pub artifact_url_template: String,
     pub pinned: bool,
+    /// How long a new version has to have been published before it is adopted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub minimum_age: Option<MinimumAge>,
+    /// Versions that are never adopted, e.g. known broken releases
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore_versions: Vec<VersionGlob>,
     pub update_scheme: VersionUpdateScheme,
+    /// Artifacts per Nix system, for sources whose artifact URL differs between systems
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub systems: BTreeMap<String, SystemArtifact>,
+    /// Further artifacts fetched at the same version, such as completions or man pages
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub artifacts: BTreeMap<String, NamedArtifact>,
+}
+
+/// The artifact of a source for a single Nix system, such as `x86_64-linux`.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct SystemArtifact {
+    /// Value of the `{arch}` placeholder
+    pub arch: String,
+    /// Value of the `{os}` placeholder
+    pub os: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub hash: String,
+}
+
+impl SystemArtifact {
+    /// Builds the artifact of a Nix system, splitting it into its architecture and OS
+    /// and mapping them through `arch_map` and `os_map` (e.g. `x86_64` to `amd64`).
+    pub fn new(system: &str, arch_map: &[(String, String)], os_map: &[(String, String)]) -> Self {
+        let (arch, os) = system.split_once('-').unwrap_or((system, ""));
+        let map = |value: &str, mapping: &[(String, String)]| {
+            mapping
+                .iter()
+                .find(|(from, _)| from == value)
+                .map_or(value, |(_, to)| to.as_str())
+                .to_string()
+        };
+
+        Self {
+            arch: map(arch, arch_map),
+            os: map(os, os_map),
+            hash: String::new(),
+        }
+    }
+}
+
+/// An additional artifact of a source, fetched at the same version as the main artifact.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct NamedArtifact {
+    pub url_template: String,
+    pub unpack: bool,
+    /// Whether the version may be updated even if this artifact can't be fetched,
+    /// in which case its hash is cleared
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub optional: bool,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub hash: String,
+}
+
+impl NamedArtifact {
+    pub fn new(url_template: &str, unpack: bool, optional: bool) -> Self {
+        Self {
+            url_template: url_template.to_string(),
+            unpack,
+            optional,
+            hash: String::new(),
+        }
+    }
+}
+
+/// Identifies one of the artifacts of a source.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ArtifactKey {
+    /// The artifact of a source without per-system artifacts
+    Main,
+    /// The artifact for a Nix system
+    System(String),
+    /// A named additional artifact
+    Named(String),
+}
+
+impl fmt::Display for ArtifactKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Main => write!(f, "artifact"),
+            Self::System(system) => write!(f, "artifact for {system}"),
+            Self::Named(name) => write!(f, "artifact {name}"),
+        }
+    }
+}
+
+/// The resolved URL of one of the artifacts of a source at a version.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ArtifactUrl {
+    pub key: ArtifactKey,
+    pub url: Url,
+    pub unpack: bool,
+    pub optional: bool,
+}
+
+/// Whether an artifact URL template has any placeholders that differ between systems.
+pub fn has_system_placeholders(artifact_url_template: &str) -> bool {
+    ["{system}", "{arch}", "{os}"]
+        .iter()
+        .any(|placeholder| artifact_url_template.contains(placeholder))
 }
 
+/// Hashes of the artifacts of a source, keyed as returned by `Source::artifact_urls`;
+/// optional artifacts that couldn't be fetched have an empty hash.
+pub type ArtifactHashes = Vec<(ArtifactKey, String)>;
+
+/// Optional artifacts that couldn't be fetched, along with the reason.
+pub type MissingArtifacts = Vec<(ArtifactKey, GetArtifactHashError)>;
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct PrefetchFileResult {
@@ -45,9 +168,14 @@ impl Source {
             latest_checked_version: version.to_string(),
             artifact_url_template: artifact_url_template.to_string(),
             hash: String::new(),
+            integrity: None,
             rev: None,
             pinned: false,
+            minimum_age: None,
+            ignore_versions: Vec::new(),
             update_scheme,
+            systems: BTreeMap::new(),
+            artifacts: BTreeMap::new(),
         }
     }
 
@@ -55,6 +183,24 @@ impl Source {
         Self { pinned, ..self }
     }
 
+    pub fn with_integrity(self, integrity: Option<String>) -> Self {
+        Self { integrity, ..self }
+    }
+
+    pub fn with_minimum_age(self, minimum_age: Option<MinimumAge>) -> Self {
+        Self {
+            minimum_age,
+            ..self
+        }
+    }
+
+    pub fn with_ignored_versions(self, ignore_versions: Vec<VersionGlob>) -> Self {
+        Self {
+            ignore_versions,
+            ..self
+        }
+    }
+
     pub fn with_rev(self, rev: Option<&str>) -> Self {
         Self {
             rev: rev.map(|r| r.to_string()),
@@ -62,28 +208,175 @@ impl Source {
         }
     }
 
-    pub fn full_url(&self, version: &str) -> Result<Url, BuildFullUrlError> {
-        let full_url = self.artifact_url_template.replace("{version}", version);
+    pub fn with_systems(
+        self,
+        systems: &[String],
+        arch_map: &[(String, String)],
+        os_map: &[(String, String)],
+    ) -> Self {
+        Self {
+            systems: systems
+                .iter()
+                .map(|system| {
+                    (
+                        system.clone(),
+                        SystemArtifact::new(system, arch_map, os_map),
+                    )
+                })
+                .collect(),
+            ..self
+        }
+    }
 
-        let full_url = if let VersionUpdateScheme::GitBranch { branch, .. } = &self.update_scheme {
-            full_url.replace("{branch}", branch)
-        } else {
-            full_url
-        };
+    pub fn with_artifacts(self, artifacts: BTreeMap<String, NamedArtifact>) -> Self {
+        Self { artifacts, ..self }
+    }
+
+    fn build_url(
+        &self,
+        template: &str,
+        version: &str,
+        system: Option<(&str, &SystemArtifact)>,
+    ) -> Result<Url, BuildFullUrlError> {
+        let mut full_url = template.replace("{version}", version);
+
+        // The branch moves on, so the artifact is fetched at the recorded commit where possible,
+        // which keeps the URL valid for the recorded hash
+        if let VersionUpdateScheme::GitBranch { branch, .. } = &self.update_scheme {
+            full_url = full_url.replace("{branch}", self.rev.as_deref().unwrap_or(branch));
+        }
+
+        if let Some((system, artifact)) = system {
+            full_url = full_url
+                .replace("{system}", system)
+                .replace("{arch}", &artifact.arch)
+                .replace("{os}", &artifact.os);
+        }
 
         Url::parse(&full_url).map_err(|parse_error| BuildFullUrlError {
             full_url,
             parse_error,
         })
     }
+
+    /// The full URL of the artifact for `version`.
+    /// For sources with per-system artifacts, this is the URL for the first system.
+    pub fn full_url(&self, version: &str) -> Result<Url, BuildFullUrlError> {
+        self.build_url(
+            &self.artifact_url_template,
+            version,
+            self.systems
+                .iter()
+                .next()
+                .map(|(system, artifact)| (system.as_str(), artifact)),
+        )
+    }
+
+    /// The full URLs of all artifacts for `version`: one per system,
+    /// or a single main artifact for sources without per-system artifacts,
+    /// followed by the named artifacts.
+    pub fn artifact_urls(&self, version: &str) -> Result<Vec<ArtifactUrl>, BuildFullUrlError> {
+        let mut urls = self.main_artifact_urls(version)?;
+
+        for (name, artifact) in &self.artifacts {
+            urls.push(ArtifactUrl {
+                key: ArtifactKey::Named(name.clone()),
+                url: self.build_url(&artifact.url_template, version, None)?,
+                unpack: artifact.unpack,
+                optional: artifact.optional,
+            });
+        }
+
+        Ok(urls)
+    }
+
+    /// The full URLs of the artifacts built from the artifact URL template, i.e. excluding named artifacts.
+    fn main_artifact_urls(&self, version: &str) -> Result<Vec<ArtifactUrl>, BuildFullUrlError> {
+        let unpack = self.update_scheme.unpack();
+
+        if self.systems.is_empty() {
+            return Ok(vec![ArtifactUrl {
+                key: ArtifactKey::Main,
+                url: self.build_url(&self.artifact_url_template, version, None)?,
+                unpack,
+                optional: false,
+            }]);
+        }
+
+        self.systems
+            .iter()
+            .map(|(system, artifact)| {
+                Ok(ArtifactUrl {
+                    key: ArtifactKey::System(system.clone()),
+                    url: self.build_url(
+                        &self.artifact_url_template,
+                        version,
+                        Some((system, artifact)),
+                    )?,
+                    unpack,
+                    optional: false,
+                })
+            })
+            .collect()
+    }
+
+    /// The recorded hash of an artifact, as returned by `artifact_urls`.
+    pub fn hash_of(&self, key: &ArtifactKey) -> Option<&str> {
+        match key {
+            ArtifactKey::Main => Some(self.hash.as_str()),
+            ArtifactKey::System(system) => self.systems.get(system).map(|a| a.hash.as_str()),
+            ArtifactKey::Named(name) => self.artifacts.get(name).map(|a| a.hash.as_str()),
+        }
+    }
+
+    /// Whether any of `hashes` differs from the recorded hash of its artifact.
+    pub fn hashes_differ(&self, hashes: &ArtifactHashes) -> bool {
+        hashes
+            .iter()
+            .any(|(key, hash)| self.hash_of(key) != Some(hash))
+    }
+
+    pub fn set_hashes(&mut self, hashes: ArtifactHashes) {
+        for (key, hash) in hashes {
+            let recorded = match key {
+                ArtifactKey::Main => Some(&mut self.hash),
+                ArtifactKey::System(system) => self.systems.get_mut(&system).map(|a| &mut a.hash),
+                ArtifactKey::Named(name) => self.artifacts.get_mut(&name).map(|a| &mut a.hash),
+            };
+            if let Some(recorded) = recorded {
+                *recorded = hash;
+            }
+        }
+    }
+
+    /// The file names of the artifacts built from the artifact URL template for `version`,
+    /// i.e. the last segments of their URLs.
+    pub fn artifact_names(&self, version: &str) -> Option<Vec<String>> {
+        self.main_artifact_urls(version)
+            .ok()?
+            .into_iter()
+            .map(|artifact| Some(artifact.url.path_segments()?.next_back()?.to_string()))
+            .collect()
+    }
 }
 
-#[derive(Default, Deserialize, Serialize)]
+#[derive(Deserialize, Serialize)]
 pub struct SourceMap {
+    #[serde(rename = "$version")]
+    schema_version: u32,
     #[serde(flatten)]
     pub inner: BTreeMap<String, Source>,
 }
 
+impl Default for SourceMap {
+    fn default() -> Self {
+        Self {
+            schema_version: CURRENT_SCHEMA_VERSION,
+            inner: BTreeMap::new(),
+        }
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum SourceMapFromFileJsonError {
     #[error("source file does not exist")]
@@ -96,10 +389,41 @@ pub enum SourceMapFromFileJsonError {
         "source file json does not confirm to nix-kunai schema at line {line}, column {column}"
     )]
     IncorrectSchema { line: usize, column: usize },
+    #[error("source file schema version is not a valid number")]
+    InvalidSchemaVersion,
+    #[error(
+        "source file has schema version {0}, but this version of nix-kunai only supports up to version {CURRENT_SCHEMA_VERSION}; update nix-kunai to use it"
+    )]
+    NewerSchemaVersion(u64),
+    #[error("source file does not conform to nix-kunai schema after migrating from version {from_version}: {error}")]
+    IncorrectMigratedSchema {
+        from_version: u32,
+        error: serde_json::Error,
+    },
     #[error("unexpected io error: {0}")]
     Io(#[from] io::Error),
 }
 
+impl From<serde_json::Error> for SourceMapFromFileJsonError {
+    fn from(e: serde_json::Error) -> Self {
+        if let Some(kind) = e.io_error_kind() {
+            io::Error::new(kind, e).into()
+        } else {
+            match e.classify() {
+                JsonErrorCategory::Io => io::Error::other(e).into(),
+                JsonErrorCategory::Syntax | JsonErrorCategory::Eof => Self::MalformedJson {
+                    line: e.line(),
+                    column: e.column(),
+                },
+                JsonErrorCategory::Data => Self::IncorrectSchema {
+                    line: e.line(),
+                    column: e.column(),
+                },
+            }
+        }
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum SourceMapWriteToFileError {
     #[error("could not write to source file; permission denied")]
@@ -110,51 +434,142 @@ pub enum SourceMapWriteToFileError {
     SerdeWriteError(serde_json::Error),
 }
 
+#[derive(Debug, Error)]
+pub enum LockSourceFileError {
+    #[error("source file is in use by another nix-kunai process (holding {})", .0.display())]
+    InUse(PathBuf),
+    #[error("could not lock source file using {}: {error}", .path.display())]
+    Io { path: PathBuf, error: io::Error },
+}
+
+/// An advisory lock on a source file, released when dropped.
+///
+/// The lock is taken on a hidden file next to the source file rather than the source file itself,
+/// since writes replace the source file and would leave other processes locking a stale file.
+pub struct SourceFileLock {
+    _file: File,
+}
+
+impl SourceFileLock {
+    /// Locks the source file at `path`, failing immediately if another process holds the lock.
+    pub fn acquire<P: AsRef<Path>>(path: P) -> Result<Self, LockSourceFileError> {
+        let path = path.as_ref();
+        let mut file_name = std::ffi::OsString::from(".");
+        file_name.push(path.file_name().unwrap_or(path.as_os_str()));
+        file_name.push(".lck");
+        let lock_path = path.with_file_name(file_name);
+
+        let file = File::options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .map_err(|error| LockSourceFileError::Io {
+                path: lock_path.clone(),
+                error,
+            })?;
+
+        match file.try_lock() {
+            Ok(()) => Ok(Self { _file: file }),
+            Err(TryLockError::WouldBlock) => Err(LockSourceFileError::InUse(lock_path)),
+            Err(TryLockError::Error(error)) => Err(LockSourceFileError::Io {
+                path: lock_path,
+                error,
+            }),
+        }
+    }
+}
+
 impl SourceMap {
-    pub fn from_reader_json<R: Read>(reader: R) -> Result<Self, serde_json::Error> {
-        serde_json::from_reader(reader)
+    /// Parses a source file, migrating it to the current schema version if needed.
+    /// The schema version the file was stored with is returned alongside it.
+    pub fn from_slice_json(bytes: &[u8]) -> Result<(Self, u32), SourceMapFromFileJsonError> {
+        let mut value: serde_json::Value = serde_json::from_slice(bytes)?;
+
+        let schema_version = match value.get(SCHEMA_VERSION_KEY) {
+            None => 0,
+            Some(version) => {
+                let version = version
+                    .as_u64()
+                    .ok_or(SourceMapFromFileJsonError::InvalidSchemaVersion)?;
+                u32::try_from(version)
+                    .ok()
+                    .filter(|&v| v <= CURRENT_SCHEMA_VERSION)
+                    .ok_or(SourceMapFromFileJsonError::NewerSchemaVersion(version))?
+            }
+        };
+
+        // Parse the original bytes where possible, since errors then point to a line and column
+        let serde_json::Value::Object(file) = &mut value else {
+            return Ok((serde_json::from_slice(bytes)?, schema_version));
+        };
+        if schema_version == CURRENT_SCHEMA_VERSION {
+            return Ok((serde_json::from_slice(bytes)?, schema_version));
+        }
+
+        migrate(file, schema_version);
+        let sources = serde_json::from_value(value).map_err(|error| {
+            SourceMapFromFileJsonError::IncorrectMigratedSchema {
+                from_version: schema_version,
+                error,
+            }
+        })?;
+
+        Ok((sources, schema_version))
     }
 
-    pub fn from_file_json<P: AsRef<Path>>(path: P) -> Result<Self, SourceMapFromFileJsonError> {
-        let file = File::open(path).map_err(|e| match e.kind() {
+    pub fn from_file_json_with_version<P: AsRef<Path>>(
+        path: P,
+    ) -> Result<(Self, u32), SourceMapFromFileJsonError> {
+        let bytes = fs::read(path).map_err(|e| match e.kind() {
             io::ErrorKind::NotFound => SourceMapFromFileJsonError::NotFound,
             io::ErrorKind::PermissionDenied => SourceMapFromFileJsonError::PermissionDenied,
             _ => SourceMapFromFileJsonError::Io(e),
         })?;
 
-        Self::from_reader_json(file).map_err(|e| {
-            if let Some(kind) = e.io_error_kind() {
-                io::Error::new(kind, e).into()
-            } else {
-                match e.classify() {
-                    JsonErrorCategory::Io => io::Error::other(e).into(),
-                    JsonErrorCategory::Syntax | JsonErrorCategory::Eof => {
-                        SourceMapFromFileJsonError::MalformedJson {
-                            line: e.line(),
-                            column: e.column(),
-                        }
-                    }
-                    JsonErrorCategory::Data => SourceMapFromFileJsonError::IncorrectSchema {
-                        line: e.line(),
-                        column: e.column(),
-                    },
-                }
-            }
-        })
+        Self::from_slice_json(&bytes)
+    }
+
+    pub fn from_file_json<P: AsRef<Path>>(path: P) -> Result<Self, SourceMapFromFileJsonError> {
+        Self::from_file_json_with_version(path).map(|(sources, _)| sources)
     }
 
     pub fn write_to_writer_pretty<W: Write>(&self, writer: W) -> Result<(), serde_json::Error> {
         serde_json::to_writer_pretty(writer, self)
     }
 
+    /// Writes the source file atomically,
+    /// by writing to a temporary file in the same directory and renaming it over the original.
+    /// An interrupted write therefore leaves the original file intact.
     pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), SourceMapWriteToFileError> {
-        let file = File::create(path).map_err(|e| match e.kind() {
+        let path = path.as_ref();
+        let map_io_error = |e: io::Error| match e.kind() {
             io::ErrorKind::PermissionDenied => SourceMapWriteToFileError::PermissionDenied,
             _ => SourceMapWriteToFileError::Io(e),
-        })?;
+        };
+
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let mut file = tempfile::Builder::new()
+            .prefix(".kunai")
+            .tempfile_in(dir)
+            .map_err(map_io_error)?;
+
+        // Temporary files are only readable by their owner, so keep the original's permissions
+        if let Ok(metadata) = fs::metadata(path) {
+            file.as_file()
+                .set_permissions(metadata.permissions())
+                .map_err(map_io_error)?;
+        }
+
+        self.write_to_writer_pretty(&mut file)
+            .map_err(SourceMapWriteToFileError::SerdeWriteError)?;
+        file.as_file().sync_all().map_err(map_io_error)?;
+        file.persist(path).map_err(|e| map_io_error(e.error))?;
 
-        self.write_to_writer_pretty(file)
-            .map_err(SourceMapWriteToFileError::SerdeWriteError)
+        Ok(())
     }
 }
 
@@ -175,9 +590,101 @@ pub enum GetArtifactHashError {
     },
     #[error("serde failed with an io error: {0}")]
     SerdeIoError(io::Error),
+    #[error("failed to download artifact: {0}")]
+    Download(HttpGetError),
+    #[error("failed to unpack artifact: {0}")]
+    Unpack(#[from] UnpackError),
+    #[error("hash {hash} of the artifact at {url} does not match the integrity {integrity} published by its registry")]
+    IntegrityMismatch {
+        url: String,
+        hash: String,
+        integrity: String,
+    },
+}
+
+pub fn get_artifact_hash_from_url(
+    url: &Url,
+    unpack: bool,
+    backend: HashBackend,
+) -> Result<String, GetArtifactHashError> {
+    match backend {
+        HashBackend::Native => native_artifact_hash(url, unpack),
+        HashBackend::Nix => nix_artifact_hash(url, unpack),
+    }
+}
+
+/// Gets the hashes of all artifacts in `urls`, as returned by `Source::artifact_urls`,
+/// failing as soon as a required one can't be fetched.
+///
+/// Optional artifacts that can't be fetched get an empty hash,
+/// and are returned along with their error as the second element.
+pub fn get_artifact_hashes(
+    urls: &[ArtifactUrl],
+    backend: HashBackend,
+) -> Result<(ArtifactHashes, MissingArtifacts), GetArtifactHashError> {
+    let mut hashes = Vec::with_capacity(urls.len());
+    let mut missing = Vec::new();
+
+    for artifact in urls {
+        match get_artifact_hash_from_url(&artifact.url, artifact.unpack, backend) {
+            Ok(hash) => hashes.push((artifact.key.clone(), hash)),
+            Err(e) if artifact.optional => {
+                hashes.push((artifact.key.clone(), String::new()));
+                missing.push((artifact.key.clone(), e));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok((hashes, missing))
+}
+
+/// Checks the hash of the main artifact against the integrity its registry published in SRI format,
+/// which may list hashes with several algorithms.
+///
+/// Only hashes of artifacts that aren't unpacked can be compared,
+/// and only if the integrity has a hash with the same algorithm; anything else passes.
+pub fn check_integrity(
+    urls: &[ArtifactUrl],
+    hashes: &ArtifactHashes,
+    integrity: &str,
+) -> Result<(), GetArtifactHashError> {
+    let Some(artifact) = urls
+        .iter()
+        .find(|a| a.key == ArtifactKey::Main && !a.unpack)
+    else {
+        return Ok(());
+    };
+    let Some((_, hash)) = hashes.iter().find(|(key, _)| *key == ArtifactKey::Main) else {
+        return Ok(());
+    };
+    let Some((algorithm, _)) = hash.split_once('-') else {
+        return Ok(());
+    };
+
+    let mismatch = integrity
+        .split_whitespace()
+        // Options may follow a hash after a question mark
+        .map(|expected| expected.split_once('?').map_or(expected, |(hash, _)| hash))
+        .filter(|expected| {
+            expected
+                .split_once('-')
+                .is_some_and(|(a, _)| a == algorithm)
+        })
+        .any(|expected| expected != hash);
+
+    if mismatch {
+        return Err(GetArtifactHashError::IntegrityMismatch {
+            url: artifact.url.to_string(),
+            hash: hash.clone(),
+            integrity: integrity.to_string(),
+        });
+    }
+
+    Ok(())
 }
 
-pub fn get_artifact_hash_from_url(url: &Url, unpack: bool) -> Result<String, GetArtifactHashError> {
+fn nix_artifact_hash(url: &Url, unpack: bool) -> Result<String, GetArtifactHashError> {
     let url_string = url.to_string();
     let mut args = vec!["store", "prefetch-file", &url_string, "--json"];
     if unpack {
@@ -1,10 +1,31 @@
-use crate::source::{get_artifact_hash_from_url, Source, SourceMap};
+use crate::age::MinimumAge;
+use crate::args::{parse_mapping, validate_artifact_url};
+use crate::crates_io::{crate_download_url_template, FetchLatestCrateError};
+use crate::feed::{FeedField, FetchLatestFeedVersionError, DEFAULT_FEED_VERSION_PATTERN};
+use crate::http_json::{fetch_http_json_version, JsonPath};
+use crate::http_regex::FetchLatestPageVersionError;
+use crate::npm::{
+    fetch_latest_npm_version, tarball_url_template, FetchLatestNpmVersionError, NpmVersionSelector,
+    DEFAULT_DIST_TAG,
+};
+use crate::prefetch::HashBackend;
+use crate::pypi::{infer_pypi_artifact_url, FetchLatestPypiReleaseError, PypiFileKind};
+use crate::releases::{FetchLatestReleaseError, GitProvider};
+use crate::source::{
+    check_integrity, get_artifact_hashes, has_system_placeholders, NamedArtifact, Source,
+    SourceFileLock, SourceMap,
+};
+use crate::tags::{
+    PrereleasePolicy, TagFilter, TagPattern, VersionGlob, VersionPattern, VersionReplacement,
+};
 use crate::updater::{
-    fetch_git_branch_commit, fetch_latest_git_tag, infer_git_url, FetchGitBranchCommitError,
-    FetchLatestGitTagError, InferGitUrlError, VersionUpdateScheme,
+    fetch_git_branch_commit, infer_git_url, FetchGitBranchCommitError, FetchLatestGitTagError,
+    GetLatestVersionError, InferGitUrlError, VersionUpdateScheme,
 };
-use clap::{Args, Subcommand, ValueEnum};
-use log::{error, info};
+use clap::{Args, Subcommand};
+use log::{error, info, warn};
+use semver::VersionReq;
+use std::collections::BTreeMap;
 use std::num::{NonZero, NonZeroUsize};
 use std::process::ExitCode;
 use thiserror::Error;
@@ -15,6 +36,13 @@ pub struct AddArgs {
     /// Mark the source as "pinned", do not update its version
     #[arg(short, long)]
     pinned: bool,
+    /// Only adopt new versions published at least this long ago, e.g. '3d', '12h' or '1w'
+    #[arg(long, value_name = "AGE")]
+    minimum_age: Option<MinimumAge>,
+    /// Never adopt this version, e.g. a known broken release;
+    /// may be a glob such as '2.1.*' (repeatable)
+    #[arg(long = "ignore-version", value_name = "VERSION")]
+    ignore_versions: Vec<VersionGlob>,
     /// Set source name manually instead of inferring
     #[arg(long)]
     source_name: Option<String>,
@@ -22,15 +50,79 @@ pub struct AddArgs {
     #[arg(short = 'f', long)]
     force: bool,
     /// Set the hash to the value provided instead of fetching
-    #[arg(long, value_name = "HASH", conflicts_with = "keep_hash")]
+    #[arg(long, value_name = "HASH", conflicts_with_all = ["keep_hash", "systems", "artifacts"])]
     force_hash: Option<String>,
     /// If a source is overridden, keep the previous hash instead of refetching
     #[arg(long, conflicts_with = "force_hash", requires = "force")]
     keep_hash: bool,
+    #[command(flatten)]
+    systems: SystemsArgs,
+    #[command(flatten)]
+    artifacts: ArtifactsArgs,
     #[command(subcommand)]
     update_scheme: UpdateSchemeArg,
 }
 
+#[derive(Args, Clone)]
+pub struct SystemsArgs {
+    /// Fetch a separate artifact for this Nix system, e.g. 'x86_64-linux' (repeatable);
+    /// the artifact URL must then contain {system}, {arch} or {os}
+    #[arg(long = "system", value_name = "NIX_SYSTEM")]
+    systems: Vec<String>,
+    /// Replace an architecture in the {arch} placeholder, e.g. 'x86_64=amd64' (repeatable)
+    #[arg(long, value_name = "FROM=TO", value_parser = parse_mapping, requires = "systems")]
+    arch_map: Vec<(String, String)>,
+    /// Replace an OS in the {os} placeholder, e.g. 'darwin=macos' (repeatable)
+    #[arg(long, value_name = "FROM=TO", value_parser = parse_mapping, requires = "systems")]
+    os_map: Vec<(String, String)>,
+}
+
+#[derive(Args, Clone)]
+pub struct ArtifactsArgs {
+    /// Also fetch an artifact named NAME from URL at the same version,
+    /// e.g. 'completions=https://example.com/completions-{version}.tar.gz' (repeatable)
+    #[arg(long = "artifact", value_name = "NAME=URL", value_parser = parse_mapping)]
+    artifacts: Vec<(String, String)>,
+    /// Unpack the named artifact (repeatable)
+    #[arg(long, value_name = "NAME", requires = "artifacts")]
+    unpack_artifact: Vec<String>,
+    /// Update the version even if the named artifact can't be fetched (repeatable)
+    #[arg(long, value_name = "NAME", requires = "artifacts")]
+    optional_artifact: Vec<String>,
+}
+
+impl ArtifactsArgs {
+    fn to_artifacts(&self) -> Result<BTreeMap<String, NamedArtifact>, String> {
+        for name in self.unpack_artifact.iter().chain(&self.optional_artifact) {
+            if !self.artifacts.iter().any(|(n, _)| n == name) {
+                return Err(format!(
+                    "no artifact named {name} was given with '--artifact'"
+                ));
+            }
+        }
+
+        self.artifacts
+            .iter()
+            .map(|(name, url_template)| {
+                Url::parse(url_template)
+                    .map_err(|e| format!("URL of artifact {name} is invalid: {e}"))?;
+                if has_system_placeholders(url_template) {
+                    return Err(format!(
+                        "URL of artifact {name} can't contain a {{system}}, {{arch}} or {{os}} placeholder"
+                    ));
+                }
+
+                let artifact = NamedArtifact::new(
+                    url_template,
+                    self.unpack_artifact.contains(name),
+                    self.optional_artifact.contains(name),
+                );
+                Ok((name.clone(), artifact))
+            })
+            .collect()
+    }
+}
+
 #[derive(Clone, Subcommand)]
 pub enum UpdateSchemeArg {
     /// Follow the latest tag in the repository
@@ -50,8 +142,66 @@ pub enum UpdateSchemeArg {
         #[arg(long, value_name = "REPOSITORY")]
         git_repo: Option<Url>,
         /// Prefix to filter tags by
+        #[arg(long, value_name = "PREFIX", conflicts_with = "tag_pattern")]
+        tag_prefix: Option<String>,
+        /// Regex that must match the whole tag, with a capture group named 'version',
+        /// e.g. 'cli/v(?<version>.+)' or 'release-(?<version>[0-9_]+)'
+        #[arg(long, value_name = "REGEX")]
+        tag_pattern: Option<TagPattern>,
+        /// Replace text in extracted versions, e.g. '_=.' to turn '1_2_3' into '1.2.3'
+        #[arg(long, value_name = "FROM=TO")]
+        version_replace: Option<VersionReplacement>,
+        /// Only follow versions matching this semver requirement,
+        /// e.g. '^1.4' (stay on 1.x), '~2.3' (only patch updates) or '<3'
+        #[arg(long, value_name = "REQUIREMENT")]
+        version_constraint: Option<VersionReq>,
+        /// Which versions to follow depending on whether they are prereleases,
+        /// as detected from the tag (e.g. '-rc1', '-beta')
+        #[arg(long, value_enum, default_value_t = PrereleasePolicy::Exclude)]
+        prereleases: PrereleasePolicy,
+        /// Unpack the artifact,
+        /// use this if the artifact link is an archive (.zip, .tar.gz, etc.)
+        #[arg(short, long)]
+        unpack: bool,
+    },
+
+    /// Follow the latest release that has a matching artifact,
+    /// from GitHub, GitLab, or Gitea/Forgejo
+    ///
+    /// Drafts are skipped, as are prereleases unless '--prereleases' says otherwise.
+    #[command(alias = "github-releases")]
+    Releases {
+        /// The URL to fetch from for a hash,
+        /// where {version} will be replaced by the version number;
+        /// its file name must match the name of a release asset
+        #[arg(value_parser = validate_artifact_url)]
+        artifact_url: String,
+        /// Initial version of the package to test for
+        /// [default: automatically fetch latest]
+        version: Option<String>,
+        /// Set source name to provided value instead of inferring from artifact URL
+        #[arg(short = 'n', long)]
+        source_name: Option<String>,
+        /// Check releases of this repository URL
+        /// instead of inferring from artifact URL
+        #[arg(long, value_name = "REPOSITORY")]
+        git_repo: Option<Url>,
+        /// Prefix to filter release tags by
         #[arg(long, value_name = "PREFIX")]
         tag_prefix: Option<String>,
+        /// Provider of the git repository, required for hosts other than GitHub and GitLab
+        /// [default: inferred from repository URL]
+        #[arg(long, value_enum)]
+        provider: Option<GitProvider>,
+        /// Base URL of the releases API, e.g. for GitHub Enterprise
+        /// [default: https://api.github.com for GitHub,
+        /// otherwise the API of the repository's host]
+        #[arg(long, value_name = "URL")]
+        api_url: Option<Url>,
+        /// Which versions to follow depending on whether they are prereleases,
+        /// as flagged by the host or detected from the tag (e.g. '-rc1', '-beta')
+        #[arg(long, value_enum, default_value_t = PrereleasePolicy::Exclude)]
+        prereleases: PrereleasePolicy,
         /// Unpack the artifact,
         /// use this if the artifact link is an archive (.zip, .tar.gz, etc.)
         #[arg(short, long)]
@@ -71,12 +221,224 @@ pub enum UpdateSchemeArg {
         #[arg(long)]
         short_hash_len: Option<NonZeroUsize>,
         /// Url to fetch artifacts from instead of inferring,
-        /// where {branch} will be replaced by the branch
+        /// where {branch} will be replaced by the commit the branch is at
         #[arg(long, conflicts_with = "provider")]
         artifact_url: Option<String>,
-        /// Provider of the git repository
+        /// Provider of the git repository, required for hosts other than GitHub and GitLab
+        /// [default: inferred from repository URL]
         #[arg(long, value_enum, conflicts_with = "artifact_url")]
-        provider: Option<GitBranchProvider>,
+        provider: Option<GitProvider>,
+    },
+
+    /// Follow the latest release of a package on PyPI
+    ///
+    /// Yanked files are skipped, as are prereleases unless '--prereleases' says otherwise.
+    Pypi {
+        /// Name of the package on PyPI
+        package: String,
+        /// Initial version of the package to test for
+        /// [default: automatically fetch latest]
+        version: Option<String>,
+        /// Set source name to provided value instead of using the package name
+        #[arg(short = 'n', long)]
+        source_name: Option<String>,
+        /// The URL to fetch from for a hash,
+        /// where {version} will be replaced by the version number;
+        /// its file name must match a file of the release
+        /// [default: the sdist of the release, or the wheel chosen with '--wheel']
+        #[arg(long, value_parser = validate_artifact_url)]
+        artifact_url: Option<String>,
+        /// Use the wheel with these compatibility tags instead of the sdist,
+        /// e.g. 'py3-none-any'
+        #[arg(long, value_name = "TAGS", conflicts_with = "artifact_url")]
+        wheel: Option<String>,
+        /// Base URL of a PyPI-compatible registry, e.g. a local mirror or devpi,
+        /// in which case '--artifact-url' is required since its file URLs can't be inferred
+        /// [default: https://pypi.org]
+        #[arg(long, value_name = "URL", requires = "artifact_url")]
+        registry_url: Option<Url>,
+        /// Which versions to follow depending on whether they are prereleases
+        /// (alpha, beta, release candidate or dev releases)
+        #[arg(long, value_enum, default_value_t = PrereleasePolicy::Exclude)]
+        prereleases: PrereleasePolicy,
+        /// Unpack the artifact,
+        /// use this if the artifact is an archive (e.g. an sdist)
+        #[arg(short, long)]
+        unpack: bool,
+    },
+
+    /// Follow the latest version of a crate on crates.io or another sparse registry
+    ///
+    /// Yanked versions are skipped, as are prereleases unless '--prereleases' says otherwise.
+    /// The artifact is the crate's '.crate' archive, which is unpacked.
+    CratesIo {
+        /// Name of the crate
+        #[arg(value_name = "CRATE")]
+        package: String,
+        /// Initial version of the crate to test for
+        /// [default: automatically fetch latest]
+        version: Option<String>,
+        /// Set source name to provided value instead of using the crate name
+        #[arg(short = 'n', long)]
+        source_name: Option<String>,
+        /// The URL to fetch from for a hash,
+        /// where {version} will be replaced by the version number
+        /// [default: the download URL of the registry]
+        #[arg(long, value_parser = validate_artifact_url)]
+        artifact_url: Option<String>,
+        /// Base URL of the sparse index of a registry
+        /// [default: https://index.crates.io]
+        #[arg(long, value_name = "URL")]
+        registry_url: Option<Url>,
+        /// Which versions to follow depending on whether they are prereleases
+        #[arg(long, value_enum, default_value_t = PrereleasePolicy::Exclude)]
+        prereleases: PrereleasePolicy,
+    },
+
+    /// Follow a package on the npm registry, or another npm-compatible registry
+    ///
+    /// The version the 'latest' dist-tag points to is followed,
+    /// unless '--dist-tag' or '--version-constraint' says otherwise.
+    /// The integrity published by the registry is recorded,
+    /// and checked against the hash where both use the same algorithm.
+    Npm {
+        /// Name of the package, e.g. 'typescript' or '@types/node'
+        package: String,
+        /// Initial version of the package to test for
+        /// [default: automatically fetch latest]
+        version: Option<String>,
+        /// Set source name to provided value instead of inferring from the package name
+        #[arg(short = 'n', long)]
+        source_name: Option<String>,
+        /// The URL to fetch from for a hash,
+        /// where {version} will be replaced by the version number
+        /// [default: the tarball URL published by the registry]
+        #[arg(long, value_parser = validate_artifact_url)]
+        artifact_url: Option<String>,
+        /// Follow this dist-tag instead of 'latest', e.g. 'next'
+        #[arg(long, value_name = "TAG", conflicts_with = "version_constraint")]
+        dist_tag: Option<String>,
+        /// Follow the newest version matching this semver requirement instead of a dist-tag,
+        /// e.g. '^5'
+        #[arg(long, value_name = "REQUIREMENT")]
+        version_constraint: Option<VersionReq>,
+        /// Base URL of an npm-compatible registry
+        /// [default: https://registry.npmjs.org]
+        #[arg(long, value_name = "URL")]
+        registry_url: Option<Url>,
+        /// Unpack the artifact,
+        /// use this if the artifact is an archive (e.g. the package tarball)
+        #[arg(short, long)]
+        unpack: bool,
+    },
+
+    /// Follow the version reported by a JSON endpoint, e.g. a vendor's update API
+    ///
+    /// The artifact URL either follows '--artifact-url',
+    /// or is taken from the response along with the version if '--artifact-url-path' is set.
+    HttpJson {
+        /// Name of the source
+        #[arg(value_name = "NAME")]
+        source_name: String,
+        /// URL of the JSON endpoint
+        endpoint: Url,
+        /// Path to the version in the response,
+        /// e.g. '$.version', '.releases[0].version' or '$.builds["linux-x64"].version'
+        #[arg(long, value_name = "PATH")]
+        version_path: JsonPath,
+        /// The URL to fetch from for a hash,
+        /// where {version} will be replaced by the version number
+        #[arg(
+            long,
+            value_parser = validate_artifact_url,
+            required_unless_present = "artifact_url_path"
+        )]
+        artifact_url: Option<String>,
+        /// Path to the artifact URL in the response, e.g. '$.url',
+        /// which then replaces the artifact URL on every update
+        #[arg(long, value_name = "PATH", conflicts_with = "artifact_url")]
+        artifact_url_path: Option<JsonPath>,
+        /// Unpack the artifact,
+        /// use this if the artifact link is an archive (.zip, .tar.gz, etc.)
+        #[arg(short, long)]
+        unpack: bool,
+    },
+
+    /// Follow the newest version found on a web page, e.g. a download page
+    ///
+    /// Every match of '--version-pattern' on the page is a candidate,
+    /// and the newest one is picked, with versions ordered like the tags of 'git-tags' sources.
+    HttpRegex {
+        /// Name of the source
+        #[arg(value_name = "NAME")]
+        source_name: String,
+        /// URL of the page listing versions
+        page_url: Url,
+        /// The URL to fetch from for a hash,
+        /// where {version} will be replaced by the version number
+        #[arg(value_parser = validate_artifact_url)]
+        artifact_url: String,
+        /// Initial version of the package to test for
+        /// [default: automatically fetch latest]
+        version: Option<String>,
+        /// Regex found anywhere on the page, with a capture group named 'version'
+        /// whose match starts with a digit, e.g. 'tool-(?<version>[0-9.]+)\.tar\.gz'
+        #[arg(long, value_name = "REGEX")]
+        version_pattern: VersionPattern,
+        /// Which versions to follow depending on whether they are prereleases,
+        /// as detected from the version (e.g. '-rc1', '-beta')
+        #[arg(long, value_enum, default_value_t = PrereleasePolicy::Exclude)]
+        prereleases: PrereleasePolicy,
+        /// Unpack the artifact,
+        /// use this if the artifact link is an archive (.zip, .tar.gz, etc.)
+        #[arg(short, long)]
+        unpack: bool,
+    },
+
+    /// Follow the newest version in an RSS or Atom feed, e.g. GitHub's releases.atom
+    ///
+    /// Unlike release APIs, feeds aren't rate limited, and the dates of their entries
+    /// allow a minimum age. Versions are ordered like the tags of 'git-tags' sources.
+    Feed {
+        /// The URL to fetch from for a hash,
+        /// where {version} will be replaced by the version number
+        #[arg(value_parser = validate_artifact_url)]
+        artifact_url: String,
+        /// Initial version of the package to test for
+        /// [default: automatically fetch latest]
+        version: Option<String>,
+        /// Set source name to provided value instead of inferring from artifact URL
+        #[arg(short = 'n', long)]
+        source_name: Option<String>,
+        /// URL of the feed
+        /// [default: the releases feed of the repository inferred from the artifact URL]
+        #[arg(long, value_name = "URL")]
+        feed_url: Option<Url>,
+        /// Follow the releases feed of this repository URL
+        /// instead of inferring it from the artifact URL
+        #[arg(long, value_name = "REPOSITORY", conflicts_with = "feed_url")]
+        git_repo: Option<Url>,
+        /// Provider of the git repository, required for hosts other than GitHub and GitLab
+        /// [default: inferred from repository URL]
+        #[arg(long, value_enum, conflicts_with = "feed_url")]
+        provider: Option<GitProvider>,
+        /// Regex finding the version of an entry, with a capture group named 'version'
+        /// whose match starts with a digit
+        /// [default: the first dotted version number]
+        #[arg(long, value_name = "REGEX")]
+        version_pattern: Option<VersionPattern>,
+        /// Which part of entries to find versions in
+        /// [default: link for the feed of a repository, whose links end with the tag, otherwise title]
+        #[arg(long, value_enum)]
+        version_from: Option<FeedField>,
+        /// Which versions to follow depending on whether they are prereleases,
+        /// as detected from the version (e.g. '-rc1', '-beta')
+        #[arg(long, value_enum, default_value_t = PrereleasePolicy::Exclude)]
+        prereleases: PrereleasePolicy,
+        /// Unpack the artifact,
+        /// use this if the artifact link is an archive (.zip, .tar.gz, etc.)
+        #[arg(short, long)]
+        unpack: bool,
     },
 
     /// Don't change the version, only the hash
@@ -97,20 +459,15 @@ pub enum UpdateSchemeArg {
     },
 }
 
-#[derive(Clone, Copy, ValueEnum)]
-pub enum GitBranchProvider {
-    Github,
-    Gitlab,
-    Gitea,
-}
-
-fn validate_artifact_url(s: &str) -> Result<String, String> {
-    Url::parse(s).map_err(|e| e.to_string())?;
-
-    Ok(s.to_string())
-}
+pub fn add(source_file_path: &str, hash_backend: HashBackend, mut args: AddArgs) -> ExitCode {
+    let _lock = match SourceFileLock::acquire(source_file_path) {
+        Ok(l) => l,
+        Err(e) => {
+            error!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
 
-pub fn add(source_file_path: &str, args: AddArgs) -> ExitCode {
     let mut sources = match SourceMap::from_file_json(source_file_path) {
         Ok(s) => s,
         Err(e) => {
@@ -137,6 +494,21 @@ pub fn add(source_file_path: &str, args: AddArgs) -> ExitCode {
             UpdateSchemeArg::GitTags {
                 source_name: None,
                 ..
+            } | UpdateSchemeArg::Releases {
+                source_name: None,
+                ..
+            } | UpdateSchemeArg::Feed {
+                source_name: None,
+                ..
+            } | UpdateSchemeArg::Pypi {
+                source_name: None,
+                ..
+            } | UpdateSchemeArg::CratesIo {
+                source_name: None,
+                ..
+            } | UpdateSchemeArg::Npm {
+                source_name: None,
+                ..
             }
         ) {
             error!("source name was inferred as '{source_name}', but said source already exists");
@@ -169,7 +541,199 @@ pub fn add(source_file_path: &str, args: AddArgs) -> ExitCode {
         None
     };
 
-    let initial_version = match build_initial_version(&args.update_scheme, initial_rev.as_deref()) {
+    if let UpdateSchemeArg::Pypi {
+        package,
+        version,
+        artifact_url: artifact_url @ None,
+        wheel,
+        prereleases,
+        ..
+    } = &mut args.update_scheme
+    {
+        let filter = TagFilter {
+            prereleases: *prereleases,
+            ignored: &args.ignore_versions,
+            ..Default::default()
+        };
+        let kind = match wheel {
+            Some(tags) => PypiFileKind::Wheel(tags),
+            None => PypiFileKind::Sdist,
+        };
+
+        match infer_pypi_artifact_url(package, &filter, version.as_deref(), &kind) {
+            Ok((latest_version, template)) => {
+                *version = Some(latest_version);
+                *artifact_url = Some(template);
+            }
+            Err(FetchLatestPypiReleaseError::NoMatchingRelease) => {
+                match wheel {
+                    Some(tags) => error!("no release of {package} fits and has a wheel for {tags}"),
+                    None => error!("no release of {package} fits and has an sdist"),
+                }
+                error!("choose a wheel with '--wheel', or define '--artifact-url' manually");
+                return ExitCode::FAILURE;
+            }
+            Err(e) => {
+                error!("could not infer artifact URL of {package}: {e}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    if let UpdateSchemeArg::CratesIo {
+        package,
+        artifact_url: artifact_url @ None,
+        registry_url,
+        ..
+    } = &mut args.update_scheme
+    {
+        match crate_download_url_template(registry_url.as_ref(), package) {
+            Ok(template) => *artifact_url = Some(template),
+            Err(e) => {
+                error!("could not get download URL of crate {package}: {e}");
+                error!("define '--artifact-url' manually");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let mut registry_integrity = None;
+    if let UpdateSchemeArg::Npm {
+        package,
+        version,
+        artifact_url,
+        dist_tag,
+        version_constraint,
+        registry_url,
+        ..
+    } = &mut args.update_scheme
+    {
+        let selector = match (&version, &version_constraint) {
+            (Some(version), _) => NpmVersionSelector::Exact(version),
+            (None, Some(constraint)) => NpmVersionSelector::Constraint(constraint),
+            (None, None) => {
+                NpmVersionSelector::DistTag(dist_tag.as_deref().unwrap_or(DEFAULT_DIST_TAG))
+            }
+        };
+
+        let release = match fetch_latest_npm_version(
+            registry_url.as_ref(),
+            package,
+            &selector,
+            &args.ignore_versions,
+        ) {
+            Ok(release) => release,
+            Err(
+                e @ (FetchLatestNpmVersionError::DistTagNotFound(_)
+                | FetchLatestNpmVersionError::DistTagIgnored { .. }
+                | FetchLatestNpmVersionError::NoMatchingVersion
+                | FetchLatestNpmVersionError::VersionNotFound(_)),
+            ) => {
+                error!("npm package {package}: {e}");
+                return ExitCode::FAILURE;
+            }
+            Err(e) => {
+                error!("could not fetch versions of npm package {package}: {e}");
+                return ExitCode::FAILURE;
+            }
+        };
+
+        if artifact_url.is_none() {
+            match tarball_url_template(&release.tarball, &release.release.version) {
+                Ok(template) => *artifact_url = Some(template),
+                Err(e) => {
+                    error!("{e}");
+                    error!("define '--artifact-url' manually");
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+        *version = Some(release.release.version);
+        registry_integrity = release.release.integrity;
+    }
+
+    if let UpdateSchemeArg::HttpJson {
+        endpoint,
+        version_path,
+        artifact_url: artifact_url @ None,
+        artifact_url_path: Some(artifact_url_path),
+        ..
+    } = &mut args.update_scheme
+    {
+        let reported = fetch_http_json_version(endpoint, version_path, Some(artifact_url_path))
+            .map(|latest| {
+                latest
+                    .artifact_url
+                    .expect("a path to the artifact URL was given")
+            });
+        match reported {
+            Ok(url) => {
+                if let Err(e) = Url::parse(&url) {
+                    error!("{endpoint} reports an invalid artifact URL {url}: {e}");
+                    return ExitCode::FAILURE;
+                }
+                *artifact_url = Some(url);
+            }
+            Err(e) => {
+                error!("could not get artifact URL from {endpoint}: {e}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let artifact_url = match &args.update_scheme {
+        UpdateSchemeArg::GitTags { artifact_url, .. }
+        | UpdateSchemeArg::Releases { artifact_url, .. }
+        | UpdateSchemeArg::HttpRegex { artifact_url, .. }
+        | UpdateSchemeArg::Feed { artifact_url, .. }
+        | UpdateSchemeArg::Static { artifact_url, .. } => Some(artifact_url),
+        UpdateSchemeArg::GitBranch { artifact_url, .. }
+        | UpdateSchemeArg::Pypi { artifact_url, .. }
+        | UpdateSchemeArg::CratesIo { artifact_url, .. }
+        | UpdateSchemeArg::Npm { artifact_url, .. }
+        | UpdateSchemeArg::HttpJson { artifact_url, .. } => artifact_url.as_ref(),
+    };
+    let template_has_system = artifact_url.is_some_and(|url| has_system_placeholders(url));
+    if template_has_system && args.systems.systems.is_empty() {
+        error!("the artifact URL contains a {{system}}, {{arch}} or {{os}} placeholder");
+        error!("add a '--system' for every Nix system to fetch an artifact for");
+        return ExitCode::FAILURE;
+    } else if !template_has_system && !args.systems.systems.is_empty() {
+        error!("'--system' was given, but the artifact URL contains no {{system}}, {{arch}} or {{os}} placeholder");
+        return ExitCode::FAILURE;
+    }
+
+    let follows_versions = !matches!(
+        args.update_scheme,
+        UpdateSchemeArg::GitBranch { .. }
+            | UpdateSchemeArg::HttpJson { .. }
+            | UpdateSchemeArg::Static { .. }
+    );
+    let has_publish_dates =
+        follows_versions && !matches!(args.update_scheme, UpdateSchemeArg::HttpRegex { .. });
+    if args.minimum_age.is_some() && !has_publish_dates {
+        error!("'--minimum-age' only applies to update schemes whose versions have publish dates");
+        return ExitCode::FAILURE;
+    }
+    if !args.ignore_versions.is_empty() && !follows_versions {
+        error!("'--ignore-version' only applies to update schemes that follow released versions");
+        return ExitCode::FAILURE;
+    }
+
+    let artifacts = match args.artifacts.to_artifacts() {
+        Ok(artifacts) => artifacts,
+        Err(e) => {
+            error!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let initial_version = match build_initial_version(
+        &args.update_scheme,
+        initial_rev.as_deref(),
+        &args.systems,
+        &args.ignore_versions,
+    ) {
         Ok(v) => v,
         Err(e) => {
             match e {
@@ -177,15 +741,35 @@ pub fn add(source_file_path: &str, args: AddArgs) -> ExitCode {
                     error!("could not infer git repository URL from artifact URL: {e}");
                     error!("define '--git-repo' manually");
                 }
-                InitialVersionError::NoTagsFitPrefix(prefix) => {
+                InitialVersionError::NoTagsFitFilter(filter) => {
+                    error!("no tags fit the {filter}");
+                    error!("ensure the repository has tags that match the tag prefix or pattern (and the version constraint, if any)");
+                }
+                InitialVersionError::NoMatchingRelease(filter) => {
                     error!(
-                        "no tags fit the tag prefix {}",
-                        match prefix {
-                            Some(prefix) => format!("'{prefix}'"),
-                            None => "(none)".to_string(),
-                        }
+                        "no releases fit the {filter} and have an asset matching the artifact URL"
+                    );
+                    error!("ensure the file name of the artifact URL matches a release asset");
+                }
+                InitialVersionError::NoMatchingPypiRelease(package) => {
+                    error!(
+                        "no releases of {package} fit and have a file matching the artifact URL"
+                    );
+                    error!(
+                        "ensure the file name of the artifact URL matches a file of the release"
                     );
-                    error!("ensure the repository has tags that begin with the correct tag prefix");
+                }
+                InitialVersionError::NoMatchingCrateVersion(package) => {
+                    error!("no versions of crate {package} fit");
+                    error!("ensure the crate has versions that aren't yanked (or prereleases, unless '--prereleases' says otherwise)");
+                }
+                InitialVersionError::NoMatchingPageVersion(page_url) => {
+                    error!("no versions found on {page_url} that fit");
+                    error!("ensure the version pattern matches the page, with a 'version' group that captures a version starting with a digit");
+                }
+                InitialVersionError::NoMatchingFeedVersion(feed_url) => {
+                    error!("no entries of feed {feed_url} have a version that fits");
+                    error!("ensure the version pattern matches the titles of entries, or their links with '--version-from link'");
                 }
                 _ => error!("{e}"),
             };
@@ -197,8 +781,14 @@ pub fn add(source_file_path: &str, args: AddArgs) -> ExitCode {
         &args.update_scheme,
         &initial_version,
         initial_rev.as_deref(),
+        &args.systems,
     ) {
-        Ok(source) => source.with_pinned(args.pinned),
+        Ok(source) => source
+            .with_pinned(args.pinned)
+            .with_minimum_age(args.minimum_age)
+            .with_ignored_versions(args.ignore_versions)
+            .with_artifacts(artifacts)
+            .with_integrity(registry_integrity.clone()),
         Err(e) => {
             error!("while building source: {e}");
             return ExitCode::FAILURE;
@@ -209,23 +799,45 @@ pub fn add(source_file_path: &str, args: AddArgs) -> ExitCode {
         new_source.hash = hash;
     } else if let Some(old_source) = sources.inner.get(&source_name) {
         new_source.hash = old_source.hash.clone();
+        for (system, artifact) in &mut new_source.systems {
+            if let Some(old_artifact) = old_source.systems.get(system) {
+                artifact.hash = old_artifact.hash.clone();
+            }
+        }
+        for (name, artifact) in &mut new_source.artifacts {
+            if let Some(old_artifact) = old_source.artifacts.get(name) {
+                artifact.hash = old_artifact.hash.clone();
+            }
+        }
     } else {
-        let full_url = match new_source.full_url(&initial_version) {
-            Ok(url) => url,
+        let urls = match new_source.artifact_urls(&initial_version) {
+            Ok(urls) => urls,
             Err(e) => {
                 error!("{e}");
                 return ExitCode::FAILURE;
             }
         };
-        info!("fetching hash from {full_url}");
-        new_source.hash =
-            match get_artifact_hash_from_url(&full_url, new_source.update_scheme.unpack()) {
-                Ok(hash) => hash,
-                Err(e) => {
-                    error!("{e}");
-                    return ExitCode::FAILURE;
+        for artifact in &urls {
+            info!("fetching hash from {}", artifact.url);
+        }
+        let hashes = get_artifact_hashes(&urls, hash_backend).and_then(|fetched| {
+            if let Some(integrity) = &registry_integrity {
+                check_integrity(&urls, &fetched.0, integrity)?;
+            }
+            Ok(fetched)
+        });
+        match hashes {
+            Ok((hashes, missing)) => {
+                for (key, e) in missing {
+                    warn!("optional {key} is missing: {e}");
                 }
-            };
+                new_source.set_hashes(hashes);
+            }
+            Err(e) => {
+                error!("{e}");
+                return ExitCode::FAILURE;
+            }
+        };
     }
 
     let old_source = sources.inner.insert(source_name.clone(), new_source);
@@ -251,11 +863,29 @@ enum SourceNameError {
 
 fn build_source_name(update_scheme: &UpdateSchemeArg) -> Result<String, SourceNameError> {
     match update_scheme {
+        // A feed given by URL has no repository, so one is only inferred if the name needs it
+        UpdateSchemeArg::Feed {
+            source_name: Some(source_name),
+            ..
+        } => Ok(source_name.clone()),
+
         UpdateSchemeArg::GitTags {
             artifact_url,
             source_name,
             git_repo,
             ..
+        }
+        | UpdateSchemeArg::Releases {
+            artifact_url,
+            source_name,
+            git_repo,
+            ..
+        }
+        | UpdateSchemeArg::Feed {
+            artifact_url,
+            source_name,
+            git_repo,
+            ..
         } => {
             let git_url = git_repo
                 .clone()
@@ -285,7 +915,29 @@ fn build_source_name(update_scheme: &UpdateSchemeArg) -> Result<String, SourceNa
                 .to_string()
         })),
 
-        UpdateSchemeArg::Static { source_name, .. } => Ok(source_name.clone()),
+        UpdateSchemeArg::Pypi {
+            package,
+            source_name,
+            ..
+        }
+        | UpdateSchemeArg::CratesIo {
+            package,
+            source_name,
+            ..
+        } => Ok(source_name.clone().unwrap_or_else(|| package.clone())),
+
+        // Scoped packages such as `@types/node` become `types-node`
+        UpdateSchemeArg::Npm {
+            package,
+            source_name,
+            ..
+        } => Ok(source_name
+            .clone()
+            .unwrap_or_else(|| package.trim_start_matches('@').replace('/', "-"))),
+
+        UpdateSchemeArg::HttpJson { source_name, .. }
+        | UpdateSchemeArg::HttpRegex { source_name, .. }
+        | UpdateSchemeArg::Static { source_name, .. } => Ok(source_name.clone()),
     }
 }
 
@@ -293,13 +945,22 @@ fn build_source_name(update_scheme: &UpdateSchemeArg) -> Result<String, SourceNa
 enum InitialVersionError {
     #[error("could not infer git repository url: {0}")]
     GetGitUrl(#[from] InferGitUrlError),
-    #[error("no tags found that fit the tag prefix")]
-    NoTagsFitPrefix(Option<String>),
-    #[error("could not fetch latest tag from {git_url}: {error}")]
-    FetchTags {
-        git_url: Url,
-        error: Box<FetchLatestGitTagError>,
-    },
+    #[error("no tags found that fit the {0}")]
+    NoTagsFitFilter(String),
+    #[error("no releases found that fit the {0} and have a matching asset")]
+    NoMatchingRelease(String),
+    #[error("no releases of {0} found that fit and have a matching file")]
+    NoMatchingPypiRelease(String),
+    #[error("no versions of crate {0} found that fit")]
+    NoMatchingCrateVersion(String),
+    #[error("no versions found on {0} that fit")]
+    NoMatchingPageVersion(String),
+    #[error("no entries of feed {0} found with a version that fits")]
+    NoMatchingFeedVersion(String),
+    #[error("could not fetch latest version: {0}")]
+    FetchLatestVersion(Box<GetLatestVersionError>),
+    #[error("{0}")]
+    BuildSource(#[from] BuildSourceError),
     #[error("branch {0} not found")]
     BranchNotFound(String),
     #[error("could not fetch commit of branch {branch} from {git_url}: {error}")]
@@ -313,34 +974,55 @@ enum InitialVersionError {
 fn build_initial_version(
     update_scheme: &UpdateSchemeArg,
     initial_rev: Option<&str>,
+    systems: &SystemsArgs,
+    ignore_versions: &[VersionGlob],
 ) -> Result<String, InitialVersionError> {
     match update_scheme {
-        UpdateSchemeArg::GitTags {
-            artifact_url,
-            version,
-            git_repo,
-            tag_prefix,
-            ..
-        } => {
-            let git_url = git_repo
-                .as_ref()
-                .map_or_else(|| infer_git_url(artifact_url), |url| Ok(url.clone()))?;
-
-            version.clone().map_or_else(
-                || {
-                    fetch_latest_git_tag(&git_url, tag_prefix.as_deref()).map_err(|e| match e {
-                        FetchLatestGitTagError::NoTagsFitFilter => {
-                            InitialVersionError::NoTagsFitPrefix(tag_prefix.clone())
-                        }
-                        _ => InitialVersionError::FetchTags {
-                            git_url,
-                            error: Box::new(e),
-                        },
+        UpdateSchemeArg::GitTags { version, .. }
+        | UpdateSchemeArg::Releases { version, .. }
+        | UpdateSchemeArg::Pypi { version, .. }
+        | UpdateSchemeArg::CratesIo { version, .. }
+        | UpdateSchemeArg::Npm { version, .. }
+        | UpdateSchemeArg::HttpRegex { version, .. }
+        | UpdateSchemeArg::Feed { version, .. } => version.clone().map_or_else(
+            || {
+                let source = build_source(update_scheme, "", None, systems)?
+                    .with_ignored_versions(ignore_versions.to_vec());
+
+                source
+                    .update_scheme
+                    .get_new_version_for(&source)
+                    .map_err(|e| match e {
+                        GetLatestVersionError::GetGitUrl(e) => InitialVersionError::GetGitUrl(e),
+                        GetLatestVersionError::FetchGitTags {
+                            error: FetchLatestGitTagError::NoTagsFitFilter,
+                            filter,
+                        } => InitialVersionError::NoTagsFitFilter(filter),
+                        GetLatestVersionError::FetchReleases {
+                            error: FetchLatestReleaseError::NoMatchingRelease,
+                            filter,
+                        } => InitialVersionError::NoMatchingRelease(filter),
+                        GetLatestVersionError::FetchPypiReleases {
+                            error: FetchLatestPypiReleaseError::NoMatchingRelease,
+                            package,
+                        } => InitialVersionError::NoMatchingPypiRelease(package),
+                        GetLatestVersionError::FetchCrateVersions {
+                            error: FetchLatestCrateError::NoMatchingVersion,
+                            package,
+                        } => InitialVersionError::NoMatchingCrateVersion(package),
+                        GetLatestVersionError::FetchPageVersions {
+                            error: FetchLatestPageVersionError::NoMatchingVersion,
+                            page_url,
+                        } => InitialVersionError::NoMatchingPageVersion(page_url),
+                        GetLatestVersionError::FetchFeedVersions {
+                            error: FetchLatestFeedVersionError::NoMatchingVersion,
+                            feed_url,
+                        } => InitialVersionError::NoMatchingFeedVersion(feed_url),
+                        _ => InitialVersionError::FetchLatestVersion(Box::new(e)),
                     })
-                },
-                Ok,
-            )
-        }
+            },
+            Ok,
+        ),
 
         UpdateSchemeArg::GitBranch {
             repository,
@@ -369,38 +1051,124 @@ fn build_initial_version(
             ))
         }
 
+        UpdateSchemeArg::HttpJson { .. } => {
+            let source = build_source(update_scheme, "", None, systems)?;
+
+            source
+                .update_scheme
+                .get_new_version_for(&source)
+                .map_err(|e| InitialVersionError::FetchLatestVersion(Box::new(e)))
+        }
+
         UpdateSchemeArg::Static { version, .. } => Ok(version.clone()),
     }
 }
 
 #[derive(Debug, Error)]
 enum BuildSourceError {
+    #[error("could not infer git repository url: {0}")]
+    GetGitUrl(#[from] InferGitUrlError),
     #[error("git repository URL does not have a base")]
     GitRepoUrlNoBase,
+    #[error("can't tell which provider hosts {0}; set it with '--provider' (e.g. '--provider gitea' for Gitea or Forgejo)")]
+    UnknownProvider(Url),
     #[error("could not get repository name")]
     GetRepositoryName,
+    #[error("could not build feed URL: {0}")]
+    InvalidFeedUrl(#[from] url::ParseError),
+}
+
+/// The provider of a repository, for hosts where it can be told from the URL.
+fn detect_provider(repository: &Url) -> Result<GitProvider, BuildSourceError> {
+    if repository.host_str().is_none() {
+        return Err(BuildSourceError::GitRepoUrlNoBase);
+    }
+
+    GitProvider::detect(repository)
+        .ok_or_else(|| BuildSourceError::UnknownProvider(repository.clone()))
 }
 
 fn build_source(
     update_scheme: &UpdateSchemeArg,
     version: &str,
     initial_commit_hash: Option<&str>,
+    systems: &SystemsArgs,
 ) -> Result<Source, BuildSourceError> {
-    match update_scheme {
+    let source = match update_scheme {
         UpdateSchemeArg::GitTags {
             artifact_url,
             git_repo,
             tag_prefix,
+            tag_pattern,
+            version_replace,
+            version_constraint,
+            prereleases,
             unpack,
             ..
         } => {
             let update_scheme = VersionUpdateScheme::GitTags {
+                prereleases: *prereleases,
                 repo_url: git_repo.clone(),
                 tag_prefix: tag_prefix.clone(),
+                tag_pattern: tag_pattern.clone(),
+                version_replacement: version_replace.clone(),
+                version_constraint: version_constraint.clone(),
                 unpack: *unpack,
             };
 
-            Ok(Source::new(version, artifact_url, update_scheme))
+            Source::new(version, artifact_url, update_scheme)
+        }
+
+        UpdateSchemeArg::Releases {
+            artifact_url,
+            git_repo,
+            tag_prefix,
+            provider,
+            api_url,
+            prereleases,
+            unpack,
+            ..
+        } => {
+            let provider = match provider {
+                Some(p) => *p,
+                None => {
+                    let git_url = git_repo
+                        .clone()
+                        .map_or_else(|| infer_git_url(artifact_url), Ok)?;
+                    detect_provider(&git_url)?
+                }
+            };
+
+            let repo_url = git_repo.clone();
+            let tag_prefix = tag_prefix.clone();
+            let api_url = api_url.clone();
+            let prereleases = *prereleases;
+            let unpack = *unpack;
+            let update_scheme = match provider {
+                GitProvider::Github => VersionUpdateScheme::GithubReleases {
+                    repo_url,
+                    tag_prefix,
+                    api_url,
+                    prereleases,
+                    unpack,
+                },
+                GitProvider::Gitlab => VersionUpdateScheme::GitlabReleases {
+                    repo_url,
+                    tag_prefix,
+                    api_url,
+                    prereleases,
+                    unpack,
+                },
+                GitProvider::Gitea => VersionUpdateScheme::GiteaReleases {
+                    repo_url,
+                    tag_prefix,
+                    api_url,
+                    prereleases,
+                    unpack,
+                },
+            };
+
+            Source::new(version, artifact_url, update_scheme)
         }
 
         UpdateSchemeArg::GitBranch {
@@ -415,38 +1183,13 @@ fn build_source(
                 Some(url) => url.clone(),
                 None => {
                     let provider = match provider {
-                        Some(p) => p,
-                        None => {
-                            let url_base = repository
-                                .host_str()
-                                .ok_or(BuildSourceError::GitRepoUrlNoBase)?;
-
-                            &match &url_base {
-                                url if url.ends_with("github.com") => GitBranchProvider::Github,
-                                url if url.ends_with("gitlab.com") => GitBranchProvider::Gitlab,
-                                _ => GitBranchProvider::Gitea,
-                            }
-                        }
+                        Some(p) => *p,
+                        None => detect_provider(repository)?,
                     };
 
-                    let repository_str = repository.as_str().trim_end_matches(".git");
-
-                    match provider {
-                        GitBranchProvider::Github | GitBranchProvider::Gitea => {
-                            format!("{repository_str}/archive/{{branch}}.tar.gz")
-                        }
-
-                        GitBranchProvider::Gitlab => {
-                            let repo_name = repository
-                                .path_segments()
-                                .and_then(|mut iter| iter.next_back())
-                                .map(|name| name.trim_end_matches(".git"))
-                                .ok_or(BuildSourceError::GetRepositoryName)?;
-                            format!(
-                                "{repository_str}/-/archive/{{branch}}/{repo_name}-{{branch}}.tar.gz"
-                            )
-                        }
-                    }
+                    provider
+                        .archive_url_template(repository, "{branch}")
+                        .ok_or(BuildSourceError::GetRepositoryName)?
                 }
             };
 
@@ -457,17 +1200,163 @@ fn build_source(
                     .unwrap_or_else(|| NonZeroUsize::new(6).expect("6 is not 0")),
             };
 
-            Ok(Source::new(version, &artifact_url, update_scheme).with_rev(initial_commit_hash))
+            Source::new(version, &artifact_url, update_scheme).with_rev(initial_commit_hash)
+        }
+
+        UpdateSchemeArg::Pypi {
+            package,
+            artifact_url,
+            registry_url,
+            prereleases,
+            unpack,
+            ..
+        } => {
+            let update_scheme = VersionUpdateScheme::Pypi {
+                unpack: *unpack,
+                package: package.clone(),
+                registry_url: registry_url.clone(),
+                prereleases: *prereleases,
+            };
+            let artifact_url = artifact_url
+                .as_deref()
+                .expect("artifact URL is inferred before building the source");
+
+            Source::new(version, artifact_url, update_scheme)
+        }
+
+        UpdateSchemeArg::CratesIo {
+            package,
+            artifact_url,
+            registry_url,
+            prereleases,
+            ..
+        } => {
+            let update_scheme = VersionUpdateScheme::CratesIo {
+                unpack: true,
+                package: package.clone(),
+                registry_url: registry_url.clone(),
+                prereleases: *prereleases,
+            };
+            let artifact_url = artifact_url
+                .as_deref()
+                .expect("artifact URL is inferred before building the source");
+
+            Source::new(version, artifact_url, update_scheme)
+        }
+
+        UpdateSchemeArg::Npm {
+            package,
+            artifact_url,
+            dist_tag,
+            version_constraint,
+            registry_url,
+            unpack,
+            ..
+        } => {
+            let update_scheme = VersionUpdateScheme::Npm {
+                unpack: *unpack,
+                package: package.clone(),
+                registry_url: registry_url.clone(),
+                dist_tag: dist_tag.clone(),
+                version_constraint: version_constraint.clone(),
+            };
+            let artifact_url = artifact_url
+                .as_deref()
+                .expect("artifact URL is inferred before building the source");
+
+            Source::new(version, artifact_url, update_scheme)
+        }
+
+        UpdateSchemeArg::HttpJson {
+            endpoint,
+            version_path,
+            artifact_url,
+            artifact_url_path,
+            unpack,
+            ..
+        } => {
+            let update_scheme = VersionUpdateScheme::HttpJson {
+                unpack: *unpack,
+                endpoint: endpoint.clone(),
+                version_path: version_path.clone(),
+                artifact_url_path: artifact_url_path.clone(),
+            };
+            let artifact_url = artifact_url.as_deref().expect(
+                "artifact URL is given or taken from the endpoint before building the source",
+            );
+
+            Source::new(version, artifact_url, update_scheme)
+        }
+
+        UpdateSchemeArg::HttpRegex {
+            page_url,
+            artifact_url,
+            version_pattern,
+            prereleases,
+            unpack,
+            ..
+        } => {
+            let update_scheme = VersionUpdateScheme::HttpRegex {
+                unpack: *unpack,
+                page_url: page_url.clone(),
+                version_pattern: version_pattern.clone(),
+                prereleases: *prereleases,
+            };
+
+            Source::new(version, artifact_url, update_scheme)
+        }
+
+        UpdateSchemeArg::Feed {
+            artifact_url,
+            feed_url,
+            git_repo,
+            provider,
+            version_pattern,
+            version_from,
+            prereleases,
+            unpack,
+            ..
+        } => {
+            let (feed_url, default_version_from) = match feed_url {
+                Some(url) => (url.clone(), FeedField::Title),
+                None => {
+                    let git_url = git_repo
+                        .clone()
+                        .map_or_else(|| infer_git_url(artifact_url), Ok)?;
+                    let provider = match provider {
+                        Some(p) => *p,
+                        None => detect_provider(&git_url)?,
+                    };
+
+                    (provider.releases_feed_url(&git_url)?, FeedField::Link)
+                }
+            };
+
+            let update_scheme = VersionUpdateScheme::Feed {
+                unpack: *unpack,
+                feed_url,
+                version_pattern: version_pattern.clone().unwrap_or_else(|| {
+                    DEFAULT_FEED_VERSION_PATTERN
+                        .parse()
+                        .expect("default pattern is valid")
+                }),
+                version_from: version_from.unwrap_or(default_version_from),
+                prereleases: *prereleases,
+            };
+
+            Source::new(version, artifact_url, update_scheme)
         }
 
         UpdateSchemeArg::Static {
             artifact_url,
             unpack,
             ..
-        } => Ok(Source::new(
+        } => Source::new(
             version,
             artifact_url,
             VersionUpdateScheme::Static { unpack: *unpack },
-        )),
-    }
+        ),
+    };
+
+    Ok(source.with_systems(&systems.systems, &systems.arch_map, &systems.os_map))
 }
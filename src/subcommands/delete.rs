@@ -1,8 +1,16 @@
-use crate::source::SourceMap;
+use crate::source::{SourceFileLock, SourceMap};
 use log::{error, info};
 use std::process::ExitCode;
 
 pub fn delete(source_file_path: &str, source_names: Vec<String>) -> ExitCode {
+    let _lock = match SourceFileLock::acquire(source_file_path) {
+        Ok(l) => l,
+        Err(e) => {
+            error!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
     let mut sources = match SourceMap::from_file_json(source_file_path) {
         Ok(s) => s,
         Err(e) => {
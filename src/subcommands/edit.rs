@@ -0,0 +1,749 @@
+use crate::age::MinimumAge;
+use crate::args::{parse_mapping, validate_artifact_url};
+use crate::feed::FeedField;
+use crate::http_json::JsonPath;
+use crate::prefetch::HashBackend;
+use crate::releases::GitProvider;
+use crate::source::{
+    get_artifact_hashes, has_system_placeholders, ArtifactKey, NamedArtifact, Source,
+    SourceFileLock, SourceMap, SystemArtifact,
+};
+use crate::tags::{PrereleasePolicy, TagPattern, VersionGlob, VersionPattern, VersionReplacement};
+use crate::updater::{fetch_git_branch_commit, VersionUpdateScheme};
+use clap::{Args, ValueEnum};
+use log::{error, info, warn};
+use semver::VersionReq;
+use std::num::NonZeroUsize;
+use std::process::ExitCode;
+use thiserror::Error;
+use url::Url;
+
+#[derive(Args)]
+pub struct EditArgs {
+    /// Name of the source to edit
+    source_name: String,
+    /// New URL to fetch from for a hash,
+    /// where {version} (or {branch}) will be replaced by the version number (or commit of the branch)
+    #[arg(long, value_name = "URL", value_parser = validate_artifact_url)]
+    artifact_url: Option<String>,
+    /// New prefix to filter tags by
+    #[arg(long, value_name = "PREFIX", conflicts_with = "no_tag_prefix")]
+    tag_prefix: Option<String>,
+    /// Remove the tag prefix
+    #[arg(long)]
+    no_tag_prefix: bool,
+    /// New regex that must match the whole tag, with a capture group named 'version'
+    #[arg(long, value_name = "REGEX", conflicts_with = "no_tag_pattern")]
+    tag_pattern: Option<TagPattern>,
+    /// Remove the tag pattern, going back to the tag prefix
+    #[arg(long)]
+    no_tag_pattern: bool,
+    /// Replace text in extracted versions, e.g. '_=.'
+    #[arg(long, value_name = "FROM=TO", conflicts_with = "no_version_replace")]
+    version_replace: Option<VersionReplacement>,
+    /// Remove the version replacement
+    #[arg(long)]
+    no_version_replace: bool,
+    /// New semver requirement that versions must match, e.g. '^1.4'
+    #[arg(
+        long,
+        value_name = "REQUIREMENT",
+        conflicts_with = "no_version_constraint"
+    )]
+    version_constraint: Option<VersionReq>,
+    /// Remove the version constraint
+    #[arg(long)]
+    no_version_constraint: bool,
+    /// Which versions to follow depending on whether they are prereleases
+    #[arg(long, value_enum)]
+    prereleases: Option<PrereleasePolicy>,
+    /// Check this repository URL for new versions
+    #[arg(long, value_name = "REPOSITORY", conflicts_with = "infer_git_repo")]
+    git_repo: Option<Url>,
+    /// Infer the repository URL from the artifact URL instead of setting it explicitly
+    #[arg(long)]
+    infer_git_repo: bool,
+    /// Base URL of the releases API
+    #[arg(long, value_name = "URL")]
+    api_url: Option<Url>,
+    /// Branch to follow
+    #[arg(long)]
+    branch: Option<String>,
+    /// Length of short hash to use in version number
+    #[arg(long)]
+    short_hash_len: Option<NonZeroUsize>,
+    /// Package to follow in the registry
+    #[arg(long)]
+    package: Option<String>,
+    /// Base URL of the package registry, e.g. a local mirror
+    #[arg(long, value_name = "URL", conflicts_with = "no_registry_url")]
+    registry_url: Option<Url>,
+    /// Go back to the default package registry
+    #[arg(long)]
+    no_registry_url: bool,
+    /// Dist-tag to follow, e.g. 'next'
+    #[arg(long, value_name = "TAG", conflicts_with = "no_dist_tag")]
+    dist_tag: Option<String>,
+    /// Go back to following the 'latest' dist-tag
+    #[arg(long)]
+    no_dist_tag: bool,
+    /// URL of the JSON endpoint reporting the version
+    #[arg(long, value_name = "URL")]
+    endpoint: Option<Url>,
+    /// Path to the version in the endpoint's response, e.g. '$.releases[0].version'
+    #[arg(long, value_name = "PATH")]
+    version_path: Option<JsonPath>,
+    /// Path to the artifact URL in the endpoint's response,
+    /// which then replaces the artifact URL on every update
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["no_artifact_url_path", "artifact_url"]
+    )]
+    artifact_url_path: Option<JsonPath>,
+    /// Stop taking the artifact URL from the endpoint's response,
+    /// keeping the current one unless '--artifact-url' is set
+    #[arg(long)]
+    no_artifact_url_path: bool,
+    /// URL of the web page listing versions
+    #[arg(long, value_name = "URL")]
+    page_url: Option<Url>,
+    /// New regex finding versions on the page or in feed entries,
+    /// with a capture group named 'version'
+    #[arg(long, value_name = "REGEX")]
+    version_pattern: Option<VersionPattern>,
+    /// URL of the RSS or Atom feed
+    #[arg(long, value_name = "URL")]
+    feed_url: Option<Url>,
+    /// Which part of feed entries to find versions in
+    #[arg(long, value_enum)]
+    version_from: Option<FeedField>,
+    /// Whether to unpack the artifact
+    #[arg(long, value_name = "BOOL")]
+    unpack: Option<bool>,
+    /// Only adopt new versions published at least this long ago, e.g. '3d'
+    #[arg(long, value_name = "AGE", conflicts_with = "no_minimum_age")]
+    minimum_age: Option<MinimumAge>,
+    /// Remove the minimum age, falling back to the one passed to `update`, if any
+    #[arg(long)]
+    no_minimum_age: bool,
+    /// Never adopt this version; may be a glob such as '2.1.*' (repeatable)
+    #[arg(long = "ignore-version", value_name = "VERSION")]
+    ignore_versions: Vec<VersionGlob>,
+    /// Stop ignoring this version, given exactly as it was ignored (repeatable)
+    #[arg(long = "unignore-version", value_name = "VERSION")]
+    unignore_versions: Vec<VersionGlob>,
+    /// Fetch a separate artifact for this Nix system, e.g. 'x86_64-linux' (repeatable)
+    #[arg(long, value_name = "NIX_SYSTEM")]
+    add_system: Vec<String>,
+    /// Stop fetching an artifact for this Nix system (repeatable)
+    #[arg(long, value_name = "NIX_SYSTEM")]
+    remove_system: Vec<String>,
+    /// Replace an architecture in the {arch} placeholder of added systems, e.g. 'x86_64=amd64'
+    #[arg(long, value_name = "FROM=TO", value_parser = parse_mapping, requires = "add_system")]
+    arch_map: Vec<(String, String)>,
+    /// Replace an OS in the {os} placeholder of added systems, e.g. 'darwin=macos'
+    #[arg(long, value_name = "FROM=TO", value_parser = parse_mapping, requires = "add_system")]
+    os_map: Vec<(String, String)>,
+    /// Also fetch an artifact named NAME from URL at the same version (repeatable);
+    /// replaces any artifact with the same name
+    #[arg(long, value_name = "NAME=URL", value_parser = parse_mapping)]
+    add_artifact: Vec<(String, String)>,
+    /// Stop fetching the named artifact (repeatable)
+    #[arg(long, value_name = "NAME")]
+    remove_artifact: Vec<String>,
+    /// Unpack the named artifact being added (repeatable)
+    #[arg(long, value_name = "NAME", requires = "add_artifact")]
+    unpack_artifact: Vec<String>,
+    /// Update the version even if the named artifact being added can't be fetched (repeatable)
+    #[arg(long, value_name = "NAME", requires = "add_artifact")]
+    optional_artifact: Vec<String>,
+    /// Keep the current hash even if the change affects the artifact
+    #[arg(long)]
+    keep_hash: bool,
+}
+
+#[derive(Debug, Error)]
+#[error("the {scheme} update scheme has no {option} option")]
+struct NotApplicableError {
+    scheme: &'static str,
+    option: &'static str,
+}
+
+/// Mutable references to the options of an update scheme,
+/// where options the scheme doesn't have are `None`.
+#[derive(Default)]
+struct SchemeOptions<'a> {
+    unpack: Option<&'a mut bool>,
+    repo_url: Option<&'a mut Option<Url>>,
+    required_repo_url: Option<&'a mut Url>,
+    tag_prefix: Option<&'a mut Option<String>>,
+    tag_pattern: Option<&'a mut Option<TagPattern>>,
+    version_replacement: Option<&'a mut Option<VersionReplacement>>,
+    version_constraint: Option<&'a mut Option<VersionReq>>,
+    prereleases: Option<&'a mut PrereleasePolicy>,
+    api_url: Option<&'a mut Option<Url>>,
+    branch: Option<&'a mut String>,
+    short_hash_length: Option<&'a mut NonZeroUsize>,
+    package: Option<&'a mut String>,
+    registry_url: Option<&'a mut Option<Url>>,
+    dist_tag: Option<&'a mut Option<String>>,
+    endpoint: Option<&'a mut Url>,
+    version_path: Option<&'a mut JsonPath>,
+    artifact_url_path: Option<&'a mut Option<JsonPath>>,
+    page_url: Option<&'a mut Url>,
+    version_pattern: Option<&'a mut VersionPattern>,
+    feed_url: Option<&'a mut Url>,
+    version_from: Option<&'a mut FeedField>,
+}
+
+impl<'a> SchemeOptions<'a> {
+    fn of(scheme: &'a mut VersionUpdateScheme) -> Self {
+        match scheme {
+            VersionUpdateScheme::GitTags {
+                unpack,
+                repo_url,
+                tag_prefix,
+                tag_pattern,
+                version_replacement,
+                version_constraint,
+                prereleases,
+            } => Self {
+                unpack: Some(unpack),
+                repo_url: Some(repo_url),
+                tag_prefix: Some(tag_prefix),
+                tag_pattern: Some(tag_pattern),
+                version_replacement: Some(version_replacement),
+                version_constraint: Some(version_constraint),
+                prereleases: Some(prereleases),
+                ..Default::default()
+            },
+
+            VersionUpdateScheme::GithubReleases {
+                unpack,
+                repo_url,
+                tag_prefix,
+                api_url,
+                prereleases,
+            }
+            | VersionUpdateScheme::GitlabReleases {
+                unpack,
+                repo_url,
+                tag_prefix,
+                api_url,
+                prereleases,
+            }
+            | VersionUpdateScheme::GiteaReleases {
+                unpack,
+                repo_url,
+                tag_prefix,
+                api_url,
+                prereleases,
+            } => Self {
+                unpack: Some(unpack),
+                repo_url: Some(repo_url),
+                tag_prefix: Some(tag_prefix),
+                api_url: Some(api_url),
+                prereleases: Some(prereleases),
+                ..Default::default()
+            },
+
+            VersionUpdateScheme::GitBranch {
+                repo_url,
+                branch,
+                short_hash_length,
+            } => Self {
+                required_repo_url: Some(repo_url),
+                branch: Some(branch),
+                short_hash_length: Some(short_hash_length),
+                ..Default::default()
+            },
+
+            VersionUpdateScheme::Pypi {
+                unpack,
+                package,
+                registry_url,
+                prereleases,
+            }
+            | VersionUpdateScheme::CratesIo {
+                unpack,
+                package,
+                registry_url,
+                prereleases,
+            } => Self {
+                unpack: Some(unpack),
+                package: Some(package),
+                registry_url: Some(registry_url),
+                prereleases: Some(prereleases),
+                ..Default::default()
+            },
+
+            VersionUpdateScheme::Npm {
+                unpack,
+                package,
+                registry_url,
+                dist_tag,
+                version_constraint,
+            } => Self {
+                unpack: Some(unpack),
+                package: Some(package),
+                registry_url: Some(registry_url),
+                dist_tag: Some(dist_tag),
+                version_constraint: Some(version_constraint),
+                ..Default::default()
+            },
+
+            VersionUpdateScheme::HttpJson {
+                unpack,
+                endpoint,
+                version_path,
+                artifact_url_path,
+            } => Self {
+                unpack: Some(unpack),
+                endpoint: Some(endpoint),
+                version_path: Some(version_path),
+                artifact_url_path: Some(artifact_url_path),
+                ..Default::default()
+            },
+
+            VersionUpdateScheme::HttpRegex {
+                unpack,
+                page_url,
+                version_pattern,
+                prereleases,
+            } => Self {
+                unpack: Some(unpack),
+                page_url: Some(page_url),
+                version_pattern: Some(version_pattern),
+                prereleases: Some(prereleases),
+                ..Default::default()
+            },
+
+            VersionUpdateScheme::Feed {
+                unpack,
+                feed_url,
+                version_pattern,
+                version_from,
+                prereleases,
+            } => Self {
+                unpack: Some(unpack),
+                feed_url: Some(feed_url),
+                version_pattern: Some(version_pattern),
+                version_from: Some(version_from),
+                prereleases: Some(prereleases),
+                ..Default::default()
+            },
+
+            VersionUpdateScheme::Static { unpack } => Self {
+                unpack: Some(unpack),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Applies the options set in `args` to `scheme`,
+/// failing if any of them don't exist for the scheme.
+fn edit_update_scheme(
+    scheme: &mut VersionUpdateScheme,
+    args: &EditArgs,
+) -> Result<(), NotApplicableError> {
+    let scheme_name = scheme.type_name();
+    let not_applicable = |option| NotApplicableError {
+        scheme: scheme_name,
+        option,
+    };
+    let options = SchemeOptions::of(scheme);
+
+    if let Some(unpack) = args.unpack {
+        *options.unpack.ok_or(not_applicable("--unpack"))? = unpack;
+    }
+
+    if args.tag_prefix.is_some() || args.no_tag_prefix {
+        *options.tag_prefix.ok_or(not_applicable("--tag-prefix"))? = args.tag_prefix.clone();
+    }
+
+    if args.tag_pattern.is_some() || args.no_tag_pattern {
+        *options.tag_pattern.ok_or(not_applicable("--tag-pattern"))? = args.tag_pattern.clone();
+    }
+
+    if args.version_replace.is_some() || args.no_version_replace {
+        *options
+            .version_replacement
+            .ok_or(not_applicable("--version-replace"))? = args.version_replace.clone();
+    }
+
+    if args.version_constraint.is_some() || args.no_version_constraint {
+        *options
+            .version_constraint
+            .ok_or(not_applicable("--version-constraint"))? = args.version_constraint.clone();
+    }
+
+    if let Some(prereleases) = args.prereleases {
+        *options.prereleases.ok_or(not_applicable("--prereleases"))? = prereleases;
+    }
+
+    if let Some(api_url) = &args.api_url {
+        *options.api_url.ok_or(not_applicable("--api-url"))? = Some(api_url.clone());
+    }
+
+    match (&args.git_repo, options.repo_url, options.required_repo_url) {
+        (Some(git_repo), Some(repo_url), _) => *repo_url = Some(git_repo.clone()),
+        (Some(git_repo), None, Some(repo_url)) => *repo_url = git_repo.clone(),
+        (Some(_), None, None) => return Err(not_applicable("--git-repo")),
+        (None, Some(repo_url), _) if args.infer_git_repo => *repo_url = None,
+        (None, None, _) if args.infer_git_repo => return Err(not_applicable("--infer-git-repo")),
+        _ => {}
+    }
+
+    if let Some(branch) = &args.branch {
+        *options.branch.ok_or(not_applicable("--branch"))? = branch.clone();
+    }
+
+    if let Some(length) = args.short_hash_len {
+        *options
+            .short_hash_length
+            .ok_or(not_applicable("--short-hash-len"))? = length;
+    }
+
+    if let Some(package) = &args.package {
+        *options.package.ok_or(not_applicable("--package"))? = package.clone();
+    }
+
+    if args.registry_url.is_some() || args.no_registry_url {
+        *options
+            .registry_url
+            .ok_or(not_applicable("--registry-url"))? = args.registry_url.clone();
+    }
+
+    if args.dist_tag.is_some() || args.no_dist_tag {
+        *options.dist_tag.ok_or(not_applicable("--dist-tag"))? = args.dist_tag.clone();
+    }
+
+    if let Some(endpoint) = &args.endpoint {
+        *options.endpoint.ok_or(not_applicable("--endpoint"))? = endpoint.clone();
+    }
+
+    if let Some(version_path) = &args.version_path {
+        *options
+            .version_path
+            .ok_or(not_applicable("--version-path"))? = version_path.clone();
+    }
+
+    if args.artifact_url_path.is_some() || args.no_artifact_url_path {
+        *options
+            .artifact_url_path
+            .ok_or(not_applicable("--artifact-url-path"))? = args.artifact_url_path.clone();
+    }
+
+    if let Some(page_url) = &args.page_url {
+        *options.page_url.ok_or(not_applicable("--page-url"))? = page_url.clone();
+    }
+
+    if let Some(version_pattern) = &args.version_pattern {
+        *options
+            .version_pattern
+            .ok_or(not_applicable("--version-pattern"))? = version_pattern.clone();
+    }
+
+    if let Some(feed_url) = &args.feed_url {
+        *options.feed_url.ok_or(not_applicable("--feed-url"))? = feed_url.clone();
+    }
+
+    if let Some(version_from) = args.version_from {
+        *options
+            .version_from
+            .ok_or(not_applicable("--version-from"))? = version_from;
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Error)]
+enum FollowMovedArtifactError {
+    #[error("the artifact URL {url} still points at the previous package {package}")]
+    Package { url: String, package: String },
+    #[error("the artifact URL {url} still points at the previous repository {repository}")]
+    Repository { url: String, repository: Url },
+}
+
+/// Updates the artifact URL template of a source whose package or branch repository changed,
+/// as the template is usually inferred from them when the source is added.
+///
+/// Templates of branch archives inferred from the previous repository are inferred again,
+/// while other templates that mention the previous package or repository can't be updated.
+fn follow_moved_artifact(
+    source: &mut Source,
+    old_package: Option<&str>,
+    old_branch_repo: Option<&Url>,
+) -> Result<(), FollowMovedArtifactError> {
+    let template = &source.artifact_url_template;
+    let options = SchemeOptions::of(&mut source.update_scheme);
+
+    if let (Some(old_package), Some(package)) = (old_package, options.package) {
+        if old_package != package && template.contains(old_package) {
+            return Err(FollowMovedArtifactError::Package {
+                url: template.clone(),
+                package: old_package.to_string(),
+            });
+        }
+    }
+
+    if let (Some(old_repo), Some(repo_url)) = (old_branch_repo, options.required_repo_url) {
+        if old_repo == repo_url {
+            return Ok(());
+        }
+
+        let inferred = GitProvider::value_variants().iter().find_map(|provider| {
+            (provider.archive_url_template(old_repo, "{branch}").as_ref() == Some(template))
+                .then(|| provider.archive_url_template(repo_url, "{branch}"))
+                .flatten()
+        });
+        let old_repo_path = old_repo.path().trim_end_matches(".git");
+        match inferred {
+            Some(inferred) => source.artifact_url_template = inferred,
+            None if template.contains(old_repo_path) => {
+                return Err(FollowMovedArtifactError::Repository {
+                    url: template.clone(),
+                    repository: old_repo.clone(),
+                });
+            }
+            None => {}
+        }
+    }
+
+    Ok(())
+}
+
+pub fn edit(source_file_path: &str, hash_backend: HashBackend, args: EditArgs) -> ExitCode {
+    let _lock = match SourceFileLock::acquire(source_file_path) {
+        Ok(l) => l,
+        Err(e) => {
+            error!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut sources = match SourceMap::from_file_json(source_file_path) {
+        Ok(s) => s,
+        Err(e) => {
+            error!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let Some(source) = sources.inner.get_mut(&args.source_name) else {
+        error!("a source named {} does not exist", args.source_name);
+        return ExitCode::FAILURE;
+    };
+    let name = &args.source_name;
+
+    // A broken URL is a likely reason to edit a source, so the old one doesn't have to be valid
+    let old_urls = source.artifact_urls(&source.version).unwrap_or_default();
+    let old_artifacts = serde_json::to_value(&source.artifacts).ok();
+    let old_scheme = serde_json::to_value(&source.update_scheme).ok();
+    let old_artifact_url = source.artifact_url_template.clone();
+    let old_systems = source.systems.keys().cloned().collect::<Vec<_>>();
+    let old_minimum_age = source.minimum_age;
+    let old_ignore_versions = source.ignore_versions.clone();
+
+    let old_package = SchemeOptions::of(&mut source.update_scheme)
+        .package
+        .map(|package| package.clone());
+    let old_branch_repo = SchemeOptions::of(&mut source.update_scheme)
+        .required_repo_url
+        .map(|repo_url| repo_url.clone());
+
+    if let Err(e) = edit_update_scheme(&mut source.update_scheme, &args) {
+        error!("{name}: {e}");
+        return ExitCode::FAILURE;
+    }
+    if let Some(artifact_url) = &args.artifact_url {
+        source.artifact_url_template = artifact_url.clone();
+    } else if let Err(e) =
+        follow_moved_artifact(source, old_package.as_deref(), old_branch_repo.as_ref())
+    {
+        error!("{name}: {e}");
+        error!(
+            "source will not be changed; pass the new artifact URL with '--artifact-url' as well"
+        );
+        return ExitCode::FAILURE;
+    } else if source.artifact_url_template != old_artifact_url {
+        info!(
+            "{name}: artifact URL changed to {}",
+            source.artifact_url_template
+        );
+    }
+    if args.minimum_age.is_some() || args.no_minimum_age {
+        if args.minimum_age.is_some() && !source.update_scheme.has_publish_dates() {
+            error!(
+                "{name}: the {} update scheme has no --minimum-age option",
+                source.update_scheme.type_name()
+            );
+            return ExitCode::FAILURE;
+        }
+        source.minimum_age = args.minimum_age;
+    }
+    for version in &args.unignore_versions {
+        let Some(index) = source.ignore_versions.iter().position(|v| v == version) else {
+            error!("{name}: version {version} is not ignored");
+            return ExitCode::FAILURE;
+        };
+        source.ignore_versions.remove(index);
+    }
+    if !args.ignore_versions.is_empty() && !source.update_scheme.follows_versions() {
+        error!(
+            "{name}: the {} update scheme has no --ignore-version option",
+            source.update_scheme.type_name()
+        );
+        return ExitCode::FAILURE;
+    }
+    for version in &args.ignore_versions {
+        if !source.ignore_versions.contains(version) {
+            source.ignore_versions.push(version.clone());
+        }
+    }
+    for system in &args.remove_system {
+        if source.systems.remove(system).is_none() {
+            error!("{name}: no artifact is fetched for system {system}");
+            return ExitCode::FAILURE;
+        }
+    }
+    for system in &args.add_system {
+        source.systems.insert(
+            system.clone(),
+            SystemArtifact::new(system, &args.arch_map, &args.os_map),
+        );
+    }
+    for artifact in &args.remove_artifact {
+        if source.artifacts.remove(artifact).is_none() {
+            error!("{name}: source has no artifact named {artifact}");
+            return ExitCode::FAILURE;
+        }
+    }
+    for artifact in args.unpack_artifact.iter().chain(&args.optional_artifact) {
+        if !args.add_artifact.iter().any(|(n, _)| n == artifact) {
+            error!("{name}: no artifact named {artifact} was given with '--add-artifact'");
+            return ExitCode::FAILURE;
+        }
+    }
+    for (artifact, url_template) in &args.add_artifact {
+        if has_system_placeholders(url_template) {
+            error!("{name}: URL of artifact {artifact} can't contain a {{system}}, {{arch}} or {{os}} placeholder");
+            return ExitCode::FAILURE;
+        }
+        source.artifacts.insert(
+            artifact.clone(),
+            NamedArtifact::new(
+                url_template,
+                args.unpack_artifact.contains(artifact),
+                args.optional_artifact.contains(artifact),
+            ),
+        );
+    }
+
+    if serde_json::to_value(&source.update_scheme).ok() == old_scheme
+        && source.artifact_url_template == old_artifact_url
+        && source.systems.keys().eq(old_systems.iter())
+        && serde_json::to_value(&source.artifacts).ok() == old_artifacts
+        && source.minimum_age == old_minimum_age
+        && source.ignore_versions == old_ignore_versions
+    {
+        info!("no changes were made to source {name}");
+        return ExitCode::SUCCESS;
+    }
+
+    // The version of a git branch source depends on the branch and short hash length,
+    // so it has to be rebuilt if either of them (or the repository) changed
+    if let VersionUpdateScheme::GitBranch {
+        repo_url,
+        branch,
+        short_hash_length,
+    } = &source.update_scheme
+    {
+        if args.git_repo.is_some() || args.branch.is_some() || args.short_hash_len.is_some() {
+            let commit = match fetch_git_branch_commit(repo_url, branch) {
+                Ok(commit) => commit,
+                Err(e) => {
+                    error!("{name}: could not fetch commit of branch {branch}: {e}");
+                    return ExitCode::FAILURE;
+                }
+            };
+
+            let version = format!("{branch}-{}", &commit[..short_hash_length.get()]);
+            if version != source.version {
+                info!("{name}: version changed: {} -> {version}", source.version);
+            }
+            source.version = version;
+            source.rev = Some(commit);
+        }
+    }
+
+    // Make the next update check the current version again under the new options
+    source.latest_checked_version = source.version.clone();
+
+    if has_system_placeholders(&source.artifact_url_template) == source.systems.is_empty() {
+        if source.systems.is_empty() {
+            error!("{name}: the artifact URL contains a {{system}}, {{arch}} or {{os}} placeholder, but no systems are set");
+        } else {
+            error!("{name}: systems are set, but the artifact URL contains no {{system}}, {{arch}} or {{os}} placeholder");
+        }
+        error!("source will not be changed");
+        return ExitCode::FAILURE;
+    }
+
+    let urls = match source.artifact_urls(&source.version) {
+        Ok(urls) => urls,
+        Err(e) => {
+            error!("{name}: {e}");
+            error!("the artifact URL template is broken; source will not be changed");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // Only artifacts that changed have to be refetched
+    let changed_urls = urls
+        .into_iter()
+        .filter(|artifact| !old_urls.contains(artifact))
+        .collect::<Vec<_>>();
+    if !changed_urls.is_empty() {
+        if args.keep_hash {
+            warn!("{name}: the artifact changed, but the hash is kept as '--keep-hash' was passed");
+        } else {
+            for artifact in &changed_urls {
+                info!("fetching hash from {}", artifact.url);
+            }
+            match get_artifact_hashes(&changed_urls, hash_backend) {
+                Ok((hashes, missing)) => {
+                    for (key, e) in missing {
+                        warn!("{name}: optional {key} is missing: {e}");
+                    }
+                    source.set_hashes(hashes);
+                    // The integrity published by a registry only applies to its own artifact
+                    if changed_urls.iter().any(|a| a.key == ArtifactKey::Main) {
+                        source.integrity = None;
+                    }
+                }
+                Err(e) => {
+                    error!("{name}: {e}");
+                    error!("source will not be changed; pass '--keep-hash' to change it anyway");
+                    return ExitCode::FAILURE;
+                }
+            };
+        }
+    }
+
+    // The single hash is unused once a source has per-system artifacts
+    if !source.systems.is_empty() {
+        source.hash.clear();
+    }
+
+    if let Err(e) = sources.write_to_file(source_file_path) {
+        error!("{e}");
+        return ExitCode::FAILURE;
+    }
+
+    info!("edited source {name}");
+
+    ExitCode::SUCCESS
+}
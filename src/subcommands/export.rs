@@ -0,0 +1,52 @@
+use crate::nix_expr::{sources_to_nix, write_nix_file};
+use crate::source::SourceMap;
+use clap::{Args, Subcommand};
+use log::{error, info};
+use std::process::ExitCode;
+
+#[derive(Args)]
+pub struct ExportArgs {
+    #[command(subcommand)]
+    format: ExportFormat,
+}
+
+#[derive(Subcommand)]
+enum ExportFormat {
+    /// A nix file with a ready-made fetcher call for each source,
+    /// to be imported with `callPackage`
+    Nix {
+        /// Write to this file instead of stdout
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<String>,
+    },
+}
+
+pub fn export(source_file_path: &str, args: ExportArgs) -> ExitCode {
+    let sources = match SourceMap::from_file_json(source_file_path) {
+        Ok(s) => s,
+        Err(e) => {
+            error!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match args.format {
+        ExportFormat::Nix { output: Some(path) } => {
+            if let Err(e) = write_nix_file(&sources, &path) {
+                error!("{e}");
+                return ExitCode::FAILURE;
+            }
+            info!("exported {} source(s) to {path}", sources.inner.len());
+        }
+
+        ExportFormat::Nix { output: None } => match sources_to_nix(&sources) {
+            Ok(nix) => print!("{nix}"),
+            Err(e) => {
+                error!("{e}");
+                return ExitCode::FAILURE;
+            }
+        },
+    }
+
+    ExitCode::SUCCESS
+}
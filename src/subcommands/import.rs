@@ -0,0 +1,1030 @@
+use crate::releases::GitProvider;
+use crate::source::{Source, SourceFileLock, SourceMap};
+use crate::tags::PrereleasePolicy;
+use crate::updater::VersionUpdateScheme;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use clap::{Args, ValueEnum};
+use log::{error, info, warn};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::num::NonZeroUsize;
+use std::process::ExitCode;
+use thiserror::Error;
+use url::Url;
+
+#[derive(Args)]
+pub struct ImportArgs {
+    /// Format of the file to import from
+    #[arg(value_enum)]
+    format: ImportFormat,
+    /// Path of the file to import from
+    /// [default: nix/sources.json for niv, npins/sources.json for npins,
+    /// yae.json for yae, flake.lock for flake]
+    path: Option<String>,
+    /// Override existing sources with the same name
+    #[arg(short, long)]
+    force: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ImportFormat {
+    Niv,
+    Npins,
+    Yae,
+    /// Direct inputs of a flake.lock
+    Flake,
+}
+
+impl ImportFormat {
+    fn default_path(self) -> &'static str {
+        match self {
+            Self::Niv => "nix/sources.json",
+            Self::Npins => "npins/sources.json",
+            Self::Yae => "yae.json",
+            Self::Flake => "flake.lock",
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+enum ImportSourceError {
+    #[error("unsupported type {0}")]
+    UnsupportedType(String),
+    #[error("missing field {0}")]
+    MissingField(&'static str),
+    #[error("hash {0} is not a valid sha256 hash")]
+    InvalidHash(String),
+    #[error("invalid URL: {0}")]
+    InvalidUrl(#[from] url::ParseError),
+    #[error("could not build an archive URL for repository {0}")]
+    NoArchiveUrl(Url),
+    #[error("can't tell which provider hosts {0}, so the URLs of its archives are unknown")]
+    UnknownProvider(Url),
+    #[error("channel {0} advances without versions to follow, so it can't be imported as a source that stays up to date; follow its branch with `nix-kunai add git-branch https://github.com/NixOS/nixpkgs {0}` instead")]
+    Channel(String),
+    #[error("{0}")]
+    Unconvertible(&'static str),
+}
+
+#[derive(Debug, Error)]
+enum ReadImportFileError {
+    #[error("could not read {path}: {error}")]
+    Io { path: String, error: io::Error },
+    #[error("{path} is not a valid file of the chosen format: {error}")]
+    Json {
+        path: String,
+        error: serde_json::Error,
+    },
+}
+
+fn read_json_file<T: DeserializeOwned>(path: &str) -> Result<T, ReadImportFileError> {
+    let file = File::open(path).map_err(|error| ReadImportFileError::Io {
+        path: path.to_string(),
+        error,
+    })?;
+
+    serde_json::from_reader(BufReader::new(file)).map_err(|error| ReadImportFileError::Json {
+        path: path.to_string(),
+        error,
+    })
+}
+
+const NIX_BASE32_CHARS: &[u8] = b"0123456789abcdfghijklmnpqrsvwxyz";
+
+/// Decodes nix's own flavor of base32, as used by older hashes.
+fn decode_nix_base32(s: &str, size: usize) -> Option<Vec<u8>> {
+    if s.len() != (size * 8 - 1) / 5 + 1 {
+        return None;
+    }
+
+    let mut bytes = vec![0u8; size];
+    for (n, c) in s.bytes().rev().enumerate() {
+        let digit = NIX_BASE32_CHARS.iter().position(|&d| d == c)? as u16;
+        let b = n * 5;
+        let (i, j) = (b / 8, b % 8);
+
+        bytes[i] |= (digit << j) as u8;
+        let carry = digit >> (8 - j);
+        if i < size - 1 {
+            bytes[i + 1] |= carry as u8;
+        } else if carry != 0 {
+            return None;
+        }
+    }
+
+    Some(bytes)
+}
+
+/// Converts a sha256 hash in SRI, nix base32 or hex format to SRI format.
+fn to_sri_hash(hash: &str) -> Result<String, ImportSourceError> {
+    let invalid = || ImportSourceError::InvalidHash(hash.to_string());
+    let hash = hash.strip_prefix("sha256:").unwrap_or(hash);
+
+    if let Some(base64) = hash.strip_prefix("sha256-") {
+        return match BASE64_STANDARD.decode(base64) {
+            Ok(bytes) if bytes.len() == 32 => Ok(hash.to_string()),
+            _ => Err(invalid()),
+        };
+    }
+
+    let bytes = if hash.len() == 64 {
+        (0..64)
+            .step_by(2)
+            .map(|i| u8::from_str_radix(hash.get(i..i + 2)?, 16).ok())
+            .collect::<Option<Vec<_>>>()
+    } else {
+        decode_nix_base32(hash, 32)
+    };
+
+    bytes
+        .map(|bytes| format!("sha256-{}", BASE64_STANDARD.encode(bytes)))
+        .ok_or_else(invalid)
+}
+
+/// Splits a tag into its prefix and the version starting at the first digit.
+fn split_tag(tag: &str) -> (Option<String>, String) {
+    let version_start = tag.find(|c: char| c.is_ascii_digit()).unwrap_or(0);
+    let (prefix, version) = tag.split_at(version_start);
+
+    (
+        (!prefix.is_empty()).then(|| prefix.to_string()),
+        version.to_string(),
+    )
+}
+
+fn short_hash_version(branch: &str, rev: &str) -> String {
+    format!("{branch}-{}", &rev[..rev.len().min(6)])
+}
+
+/// The provider of a repository, as given by the imported file or detected from its host,
+/// since archive URLs of unknown hosts can't be guessed.
+fn repository_provider(
+    repository: &Url,
+    provider: Option<GitProvider>,
+) -> Result<GitProvider, ImportSourceError> {
+    provider
+        .or_else(|| GitProvider::detect(repository))
+        .ok_or_else(|| ImportSourceError::UnknownProvider(repository.clone()))
+}
+
+fn git_branch_source(
+    repository: Url,
+    provider: Option<GitProvider>,
+    branch: &str,
+    rev: &str,
+    hash: &str,
+) -> Result<Source, ImportSourceError> {
+    let artifact_url = repository_provider(&repository, provider)?
+        .archive_url_template(&repository, "{branch}")
+        .ok_or_else(|| ImportSourceError::NoArchiveUrl(repository.clone()))?;
+
+    let mut source = Source::new(
+        &short_hash_version(branch, rev),
+        &artifact_url,
+        VersionUpdateScheme::GitBranch {
+            repo_url: repository,
+            branch: branch.to_string(),
+            short_hash_length: NonZeroUsize::new(6).expect("6 is not 0"),
+        },
+    )
+    .with_rev(Some(rev));
+    source.hash = to_sri_hash(hash)?;
+
+    Ok(source)
+}
+
+fn static_source(url: &str, unpack: bool, hash: &str) -> Result<Source, ImportSourceError> {
+    Url::parse(url)?;
+
+    let mut source = Source::new("static", url, VersionUpdateScheme::Static { unpack });
+    source.hash = to_sri_hash(hash)?;
+
+    Ok(source)
+}
+
+#[derive(Deserialize)]
+struct NivSource {
+    #[serde(rename = "type")]
+    source_type: Option<String>,
+    owner: Option<String>,
+    repo: Option<String>,
+    branch: Option<String>,
+    rev: Option<String>,
+    version: Option<String>,
+    url: Option<String>,
+    url_template: Option<String>,
+    sha256: Option<String>,
+    #[serde(flatten)]
+    attributes: BTreeMap<String, serde_json::Value>,
+}
+
+fn convert_niv(source: NivSource) -> Result<Source, ImportSourceError> {
+    let unpack = match source.source_type.as_deref() {
+        Some("tarball") | None => true,
+        Some("file") => false,
+        Some(other) => return Err(ImportSourceError::UnsupportedType(other.to_string())),
+    };
+    let hash = source
+        .sha256
+        .as_deref()
+        .ok_or(ImportSourceError::MissingField("sha256"))?;
+
+    // niv templates use <attribute> placeholders for any attribute of the source
+    let url_template = source.url_template.as_deref().map(|template| {
+        let mut template = template.replace("<version>", "{version}");
+        for (key, value) in [("owner", &source.owner), ("repo", &source.repo)] {
+            if let Some(value) = value {
+                template = template.replace(&format!("<{key}>"), value);
+            }
+        }
+        for (key, value) in &source.attributes {
+            if let Some(value) = value.as_str() {
+                template = template.replace(&format!("<{key}>"), value);
+            }
+        }
+        template
+    });
+
+    match (&source.owner, &source.repo, &source.branch, &source.rev) {
+        (Some(owner), Some(repo), Some(branch), Some(rev)) if source.version.is_none() => {
+            let repository = Url::parse(&format!("https://github.com/{owner}/{repo}"))?;
+            git_branch_source(repository, Some(GitProvider::Github), branch, rev, hash)
+        }
+
+        _ => match (url_template, &source.version) {
+            (Some(template), Some(version)) if template.contains("{version}") => {
+                // The tag prefix is stripped from the version, so it has to be part of the template
+                let (tag_prefix, version) = split_tag(version);
+                let template = template.replace(
+                    "{version}",
+                    &format!("{}{{version}}", tag_prefix.as_deref().unwrap_or("")),
+                );
+                Url::parse(&template.replace("{version}", &version))?;
+
+                let repo_url = match (&source.owner, &source.repo) {
+                    (Some(owner), Some(repo)) => {
+                        Some(Url::parse(&format!("https://github.com/{owner}/{repo}"))?)
+                    }
+                    _ => None,
+                };
+                let mut new_source = Source::new(
+                    &version,
+                    &template,
+                    VersionUpdateScheme::GitTags {
+                        unpack,
+                        repo_url,
+                        tag_prefix,
+                        tag_pattern: None,
+                        version_replacement: None,
+                        version_constraint: None,
+                        prereleases: PrereleasePolicy::default(),
+                    },
+                );
+                new_source.hash = to_sri_hash(hash)?;
+
+                Ok(new_source)
+            }
+
+            _ => {
+                let url = source
+                    .url
+                    .as_deref()
+                    .ok_or(ImportSourceError::MissingField("url"))?;
+                static_source(url, unpack, hash)
+            }
+        },
+    }
+}
+
+#[derive(Deserialize)]
+struct NpinsFile {
+    pins: BTreeMap<String, NpinsPin>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum NpinsPin {
+    Git {
+        repository: NpinsRepository,
+        branch: String,
+        revision: String,
+        hash: String,
+    },
+    GitRelease {
+        repository: NpinsRepository,
+        release_prefix: Option<String>,
+        version: Option<String>,
+        #[serde(default)]
+        pre_releases: bool,
+        hash: String,
+    },
+    Channel {
+        name: String,
+    },
+    Tarball {
+        url: String,
+        hash: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum NpinsRepository {
+    GitHub {
+        owner: String,
+        repo: String,
+    },
+    GitLab {
+        repo_path: String,
+        server: Url,
+    },
+    Forgejo {
+        server: Url,
+        owner: String,
+        repo: String,
+    },
+    Git {
+        url: Url,
+    },
+}
+
+impl NpinsRepository {
+    fn url(&self) -> Result<Url, url::ParseError> {
+        match self {
+            Self::GitHub { owner, repo } => {
+                Url::parse(&format!("https://github.com/{owner}/{repo}"))
+            }
+            Self::GitLab { repo_path, server } => server.join(repo_path),
+            Self::Forgejo {
+                server,
+                owner,
+                repo,
+            } => server.join(&format!("{owner}/{repo}")),
+            Self::Git { url } => Ok(url.clone()),
+        }
+    }
+
+    /// The provider of the repository, unless it is a plain git repository.
+    fn provider(&self) -> Option<GitProvider> {
+        match self {
+            Self::GitHub { .. } => Some(GitProvider::Github),
+            Self::GitLab { .. } => Some(GitProvider::Gitlab),
+            Self::Forgejo { .. } => Some(GitProvider::Gitea),
+            Self::Git { .. } => None,
+        }
+    }
+}
+
+fn convert_npins(pin: NpinsPin) -> Result<Source, ImportSourceError> {
+    match pin {
+        NpinsPin::Git {
+            repository,
+            branch,
+            revision,
+            hash,
+        } => git_branch_source(
+            repository.url()?,
+            repository.provider(),
+            &branch,
+            &revision,
+            &hash,
+        ),
+
+        NpinsPin::GitRelease {
+            repository,
+            release_prefix,
+            version,
+            pre_releases,
+            hash,
+        } => {
+            let provider = repository.provider();
+            let repository = repository.url()?;
+            let tag = version.ok_or(ImportSourceError::MissingField("version"))?;
+            let tag = match &release_prefix {
+                Some(prefix) => tag.strip_prefix(prefix.as_str()).unwrap_or(&tag),
+                None => &tag,
+            };
+            let (version_prefix, version) = split_tag(tag);
+            let tag_prefix = match (release_prefix, version_prefix) {
+                (None, None) => None,
+                (release_prefix, version_prefix) => {
+                    Some(release_prefix.unwrap_or_default() + &version_prefix.unwrap_or_default())
+                }
+            };
+
+            let artifact_url = repository_provider(&repository, provider)?
+                .archive_url_template(
+                    &repository,
+                    &format!("{}{{version}}", tag_prefix.as_deref().unwrap_or("")),
+                )
+                .ok_or_else(|| ImportSourceError::NoArchiveUrl(repository.clone()))?;
+
+            let mut source = Source::new(
+                &version,
+                &artifact_url,
+                VersionUpdateScheme::GitTags {
+                    unpack: true,
+                    repo_url: Some(repository),
+                    tag_prefix,
+                    tag_pattern: None,
+                    version_replacement: None,
+                    version_constraint: None,
+                    prereleases: if pre_releases {
+                        PrereleasePolicy::Include
+                    } else {
+                        PrereleasePolicy::Exclude
+                    },
+                },
+            );
+            source.hash = to_sri_hash(&hash)?;
+
+            Ok(source)
+        }
+
+        // A static source would keep the hash of the channel's tarball at the time it was pinned,
+        // while the tarball behind the channel URL changes as the channel advances
+        NpinsPin::Channel { name } => Err(ImportSourceError::Channel(name)),
+
+        NpinsPin::Tarball { url, hash } => static_source(&url, true, &hash),
+
+        NpinsPin::Other => Err(ImportSourceError::Unconvertible(
+            "pin type has no equivalent update scheme",
+        )),
+    }
+}
+
+#[derive(Deserialize)]
+struct YaeSource {
+    url: Option<String>,
+    url_template: Option<String>,
+    hash: String,
+    #[serde(default)]
+    unpack: bool,
+    #[serde(rename = "type")]
+    source_type: Option<String>,
+    version: Option<String>,
+    trim_tag_prefix: Option<String>,
+    #[serde(default)]
+    pinned: bool,
+}
+
+fn convert_yae(source: YaeSource) -> Result<Source, ImportSourceError> {
+    let new_source = match (source.source_type.as_deref(), source.version) {
+        (Some("git"), Some(version)) => {
+            let template = source
+                .url_template
+                .ok_or(ImportSourceError::MissingField("url_template"))?;
+            Url::parse(&template.replace("{version}", &version))?;
+
+            let mut new_source = Source::new(
+                &version,
+                &template,
+                VersionUpdateScheme::GitTags {
+                    unpack: source.unpack,
+                    repo_url: None,
+                    tag_prefix: source.trim_tag_prefix,
+                    tag_pattern: None,
+                    version_replacement: None,
+                    version_constraint: None,
+                    prereleases: PrereleasePolicy::default(),
+                },
+            );
+            new_source.hash = to_sri_hash(&source.hash)?;
+            new_source
+        }
+
+        (Some("git"), None) => return Err(ImportSourceError::MissingField("version")),
+
+        (_, version) => {
+            let url = source
+                .url_template
+                .or(source.url)
+                .ok_or(ImportSourceError::MissingField("url"))?;
+            let mut new_source = static_source(&url, source.unpack, &source.hash)?;
+            if let Some(version) = version {
+                new_source.version = version.clone();
+                new_source.latest_checked_version = version;
+            }
+            new_source
+        }
+    };
+
+    Ok(new_source.with_pinned(source.pinned))
+}
+
+#[derive(Deserialize)]
+struct FlakeLock {
+    nodes: BTreeMap<String, FlakeNode>,
+    root: String,
+}
+
+#[derive(Deserialize)]
+struct FlakeNode {
+    #[serde(default)]
+    inputs: BTreeMap<String, serde_json::Value>,
+    locked: Option<FlakeRef>,
+    original: Option<FlakeRef>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FlakeRef {
+    #[serde(rename = "type")]
+    ref_type: String,
+    owner: Option<String>,
+    repo: Option<String>,
+    host: Option<String>,
+    url: Option<String>,
+    #[serde(rename = "ref")]
+    git_ref: Option<String>,
+    rev: Option<String>,
+    nar_hash: Option<String>,
+}
+
+fn convert_flake_input(node: &FlakeNode) -> Result<Source, ImportSourceError> {
+    let locked = node
+        .locked
+        .as_ref()
+        .ok_or(ImportSourceError::MissingField("locked"))?;
+    let original = node
+        .original
+        .as_ref()
+        .ok_or(ImportSourceError::MissingField("original"))?;
+    let hash = locked
+        .nar_hash
+        .as_deref()
+        .ok_or(ImportSourceError::MissingField("narHash"))?;
+
+    match locked.ref_type.as_str() {
+        "github" | "gitlab" | "git" => {
+            let repository = match (&locked.owner, &locked.repo, &locked.url) {
+                (Some(owner), Some(repo), _) => {
+                    let default_host = if locked.ref_type == "github" {
+                        "github.com"
+                    } else {
+                        "gitlab.com"
+                    };
+                    let host = locked.host.as_deref().unwrap_or(default_host);
+                    Url::parse(&format!("https://{host}/{owner}/{repo}"))?
+                }
+                (_, _, Some(url)) => Url::parse(url.trim_start_matches("git+"))?,
+                _ => return Err(ImportSourceError::MissingField("url")),
+            };
+            if original.rev.is_some() {
+                return Err(ImportSourceError::Unconvertible(
+                    "input is pinned to a specific revision",
+                ));
+            }
+            let branch = original
+                .git_ref
+                .as_deref()
+                .ok_or(ImportSourceError::Unconvertible(
+                    "input does not follow a named branch",
+                ))?;
+            let rev = locked
+                .rev
+                .as_deref()
+                .ok_or(ImportSourceError::MissingField("rev"))?;
+            let provider = match locked.ref_type.as_str() {
+                "github" => Some(GitProvider::Github),
+                "gitlab" => Some(GitProvider::Gitlab),
+                _ => None,
+            };
+
+            git_branch_source(repository, provider, branch, rev, hash)
+        }
+
+        ref_type @ ("tarball" | "file") => {
+            let url = original
+                .url
+                .as_deref()
+                .ok_or(ImportSourceError::MissingField("url"))?;
+            static_source(url, ref_type == "tarball", hash)
+        }
+
+        other => Err(ImportSourceError::UnsupportedType(other.to_string())),
+    }
+}
+
+/// The name of an imported source and the result of converting it.
+type ImportedSource = (String, Result<Source, ImportSourceError>);
+
+fn read_sources(
+    format: ImportFormat,
+    path: &str,
+) -> Result<Vec<ImportedSource>, ReadImportFileError> {
+    Ok(match format {
+        ImportFormat::Niv => read_json_file::<BTreeMap<String, NivSource>>(path)?
+            .into_iter()
+            .map(|(name, source)| (name, convert_niv(source)))
+            .collect(),
+
+        ImportFormat::Npins => read_json_file::<NpinsFile>(path)?
+            .pins
+            .into_iter()
+            .map(|(name, pin)| (name, convert_npins(pin)))
+            .collect(),
+
+        ImportFormat::Yae => read_json_file::<BTreeMap<String, YaeSource>>(path)?
+            .into_iter()
+            .map(|(name, source)| (name, convert_yae(source)))
+            .collect(),
+
+        ImportFormat::Flake => {
+            let lock = read_json_file::<FlakeLock>(path)?;
+            let Some(root) = lock.nodes.get(&lock.root) else {
+                return Ok(vec![]);
+            };
+
+            root.inputs
+                .iter()
+                // Inputs that follow other inputs are lists instead of node names
+                .filter_map(|(name, node_name)| Some((name, lock.nodes.get(node_name.as_str()?)?)))
+                .map(|(name, node)| (name.clone(), convert_flake_input(node)))
+                .collect()
+        }
+    })
+}
+
+pub fn import(source_file_path: &str, args: ImportArgs) -> ExitCode {
+    let _lock = match SourceFileLock::acquire(source_file_path) {
+        Ok(l) => l,
+        Err(e) => {
+            error!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut sources = match SourceMap::from_file_json(source_file_path) {
+        Ok(s) => s,
+        Err(e) => {
+            error!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let path = args
+        .path
+        .as_deref()
+        .unwrap_or_else(|| args.format.default_path());
+    let imported = match read_sources(args.format, path) {
+        Ok(imported) => imported,
+        Err(e) => {
+            error!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut added = 0;
+    let mut failed = vec![];
+
+    for (name, source) in imported {
+        match source {
+            Ok(_) if sources.inner.contains_key(&name) && !args.force => {
+                warn!("a source named {name} already exists; skipping (add '--force' to override)");
+                failed.push(name);
+            }
+            Ok(source) => {
+                info!(
+                    "imported {name} as a {} source (version {})",
+                    source.update_scheme.type_name(),
+                    source.version
+                );
+                sources.inner.insert(name, source);
+                added += 1;
+            }
+            Err(e) => {
+                warn!("could not convert {name}: {e}");
+                failed.push(name);
+            }
+        }
+    }
+
+    if added > 0 {
+        if let Err(e) = sources.write_to_file(source_file_path) {
+            error!("{e}");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    info!(
+        "imported {added} source(s) from {path} ({} not imported)",
+        failed.len()
+    );
+    if !failed.is_empty() {
+        warn!("sources not imported: {}", failed.join(", "));
+        warn!("these sources must be added manually");
+    }
+
+    ExitCode::SUCCESS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// sha256 of the empty string
+    const EMPTY_SRI: &str = "sha256-47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=";
+
+    #[test]
+    fn nix_base32_hashes_are_converted() {
+        assert_eq!(
+            to_sri_hash("0mdqa9w1p6cmli6976v4wi0sw9r4p5prkj7lzfd1877wk11c9c73").unwrap(),
+            EMPTY_SRI
+        );
+        assert_eq!(
+            to_sri_hash("sha256:00xyyr3fi8l6hb839bv3f7yb86yjv7xi1cgh1xnhipym4asvb4aq").unwrap(),
+            "sha256-WJG1tSLV3whtD/CxEPvZ0hu0/HFjrzTQgoai6Eb2vgM="
+        );
+    }
+
+    #[test]
+    fn hex_and_sri_hashes_are_converted() {
+        assert_eq!(
+            to_sri_hash("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+                .unwrap(),
+            EMPTY_SRI
+        );
+        assert_eq!(to_sri_hash(EMPTY_SRI).unwrap(), EMPTY_SRI);
+    }
+
+    #[test]
+    fn invalid_nix_base32_is_rejected() {
+        // Wrong length, characters outside the alphabet (`e`, `o`, `t`, `u`),
+        // and a first digit whose bits don't fit in 32 bytes
+        assert_eq!(
+            decode_nix_base32("0mdqa9w1p6cmli6976v4wi0sw9r4p5prkj7lzfd1877wk11c9c7", 32),
+            None
+        );
+        assert_eq!(
+            decode_nix_base32("emdqa9w1p6cmli6976v4wi0sw9r4p5prkj7lzfd1877wk11c9c73", 32),
+            None
+        );
+        assert_eq!(
+            decode_nix_base32("zmdqa9w1p6cmli6976v4wi0sw9r4p5prkj7lzfd1877wk11c9c73", 32),
+            None
+        );
+        assert!(to_sri_hash("sha256-aGVsbG8=").is_err());
+    }
+
+    const REV: &str = "0123456789abcdef0123456789abcdef01234567";
+
+    fn parse<T: DeserializeOwned>(json: serde_json::Value) -> T {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn niv_branches_follow_github() {
+        let source = convert_niv(parse(json!({
+            "type": "tarball",
+            "owner": "nmattia",
+            "repo": "niv",
+            "branch": "master",
+            "rev": REV,
+            "sha256": EMPTY_SRI,
+            "url_template": "https://github.com/<owner>/<repo>/archive/<rev>.tar.gz",
+        })))
+        .unwrap();
+
+        assert!(matches!(
+            &source.update_scheme,
+            VersionUpdateScheme::GitBranch { branch, .. } if branch == "master"
+        ));
+        assert_eq!(source.version, "master-012345");
+        assert_eq!(source.rev.as_deref(), Some(REV));
+        assert_eq!(
+            source.artifact_url_template,
+            "https://github.com/nmattia/niv/archive/{branch}.tar.gz"
+        );
+        assert_eq!(source.hash, EMPTY_SRI);
+    }
+
+    #[test]
+    fn niv_versions_keep_their_tag_prefix_in_the_template() {
+        let source = convert_niv(parse(json!({
+            "type": "file",
+            "version": "v1.2.3",
+            "url_template": "https://example.com/<name>-<version>.tar.gz",
+            "name": "tool",
+            "sha256": EMPTY_SRI,
+        })))
+        .unwrap();
+
+        assert!(matches!(
+            &source.update_scheme,
+            VersionUpdateScheme::GitTags { unpack: false, tag_prefix: Some(prefix), .. } if prefix == "v"
+        ));
+        assert_eq!(source.version, "1.2.3");
+        assert_eq!(
+            source.artifact_url_template,
+            "https://example.com/tool-v{version}.tar.gz"
+        );
+    }
+
+    #[test]
+    fn niv_urls_without_versions_are_static() {
+        let source = convert_niv(parse(json!({
+            "url": "https://example.com/tool.tar.gz",
+            "sha256": EMPTY_SRI,
+        })))
+        .unwrap();
+
+        assert!(matches!(
+            source.update_scheme,
+            VersionUpdateScheme::Static { unpack: true }
+        ));
+        assert_eq!(
+            source.artifact_url_template,
+            "https://example.com/tool.tar.gz"
+        );
+    }
+
+    #[test]
+    fn npins_git_pins_use_the_provider_of_their_repository() {
+        let source = convert_npins(parse(json!({
+            "type": "Git",
+            "repository": {"type": "Forgejo", "server": "https://codeberg.org/", "owner": "owner", "repo": "repo"},
+            "branch": "main",
+            "revision": REV,
+            "hash": EMPTY_SRI,
+        })))
+        .unwrap();
+
+        assert_eq!(source.version, "main-012345");
+        assert_eq!(
+            source.artifact_url_template,
+            "https://codeberg.org/owner/repo/archive/{branch}.tar.gz"
+        );
+    }
+
+    #[test]
+    fn npins_plain_git_repositories_on_unknown_hosts_are_not_guessed() {
+        let result = convert_npins(parse(json!({
+            "type": "Git",
+            "repository": {"type": "Git", "url": "https://git.sr.ht/~owner/repo"},
+            "branch": "main",
+            "revision": REV,
+            "hash": EMPTY_SRI,
+        })));
+
+        assert!(matches!(result, Err(ImportSourceError::UnknownProvider(_))));
+    }
+
+    #[test]
+    fn npins_releases_follow_tags() {
+        let source = convert_npins(parse(json!({
+            "type": "GitRelease",
+            "repository": {"type": "GitLab", "repo_path": "group/repo", "server": "https://gitlab.example.org/"},
+            "pre_releases": true,
+            "release_prefix": null,
+            "version": "v2.0.0",
+            "hash": EMPTY_SRI,
+        })))
+        .unwrap();
+
+        assert!(matches!(
+            &source.update_scheme,
+            VersionUpdateScheme::GitTags {
+                tag_prefix: Some(prefix),
+                prereleases: PrereleasePolicy::Include,
+                ..
+            } if prefix == "v"
+        ));
+        assert_eq!(source.version, "2.0.0");
+        assert_eq!(
+            source.artifact_url_template,
+            "https://gitlab.example.org/group/repo/-/archive/v{version}/repo-v{version}.tar.gz"
+        );
+    }
+
+    #[test]
+    fn npins_channels_are_refused() {
+        let result = convert_npins(parse(json!({
+            "type": "Channel",
+            "name": "nixos-unstable",
+            "url": "https://releases.nixos.org/nixos/unstable/nixos-24.11pre1/nixexprs.tar.xz",
+            "hash": EMPTY_SRI,
+        })));
+
+        assert!(
+            matches!(result, Err(ImportSourceError::Channel(name)) if name == "nixos-unstable")
+        );
+    }
+
+    #[test]
+    fn npins_tarballs_are_static() {
+        let source = convert_npins(parse(json!({
+            "type": "Tarball",
+            "url": "https://example.com/tool.tar.gz",
+            "hash": EMPTY_SRI,
+        })))
+        .unwrap();
+
+        assert!(matches!(
+            source.update_scheme,
+            VersionUpdateScheme::Static { unpack: true }
+        ));
+    }
+
+    #[test]
+    fn yae_git_sources_follow_tags() {
+        let source = convert_yae(parse(json!({
+            "type": "git",
+            "version": "1.2.0",
+            "url_template": "https://github.com/owner/repo/archive/v{version}.tar.gz",
+            "trim_tag_prefix": "v",
+            "unpack": true,
+            "pinned": true,
+            "hash": EMPTY_SRI,
+        })))
+        .unwrap();
+
+        assert!(matches!(
+            &source.update_scheme,
+            VersionUpdateScheme::GitTags { unpack: true, tag_prefix: Some(prefix), .. } if prefix == "v"
+        ));
+        assert_eq!(source.version, "1.2.0");
+        assert!(source.pinned);
+    }
+
+    #[test]
+    fn yae_other_sources_are_static() {
+        let source = convert_yae(parse(json!({
+            "url": "https://example.com/tool.tar.gz",
+            "version": "1.0",
+            "hash": EMPTY_SRI,
+        })))
+        .unwrap();
+
+        assert!(matches!(
+            source.update_scheme,
+            VersionUpdateScheme::Static { unpack: false }
+        ));
+        assert_eq!(source.version, "1.0");
+    }
+
+    fn flake_node(locked: serde_json::Value, original: serde_json::Value) -> FlakeNode {
+        parse(json!({"locked": locked, "original": original}))
+    }
+
+    #[test]
+    fn flake_inputs_following_branches_are_converted() {
+        let node = flake_node(
+            json!({"type": "github", "owner": "NixOS", "repo": "nixpkgs", "rev": REV, "narHash": EMPTY_SRI}),
+            json!({"type": "github", "owner": "NixOS", "repo": "nixpkgs", "ref": "nixos-unstable"}),
+        );
+        let source = convert_flake_input(&node).unwrap();
+
+        assert_eq!(source.version, "nixos-unstable-012345");
+        assert_eq!(
+            source.artifact_url_template,
+            "https://github.com/NixOS/nixpkgs/archive/{branch}.tar.gz"
+        );
+        assert_eq!(source.hash, EMPTY_SRI);
+    }
+
+    #[test]
+    fn flake_inputs_on_unknown_hosts_or_pinned_revisions_are_refused() {
+        let unknown_host = flake_node(
+            json!({"type": "git", "url": "https://git.sr.ht/~owner/repo", "rev": REV, "narHash": EMPTY_SRI}),
+            json!({"type": "git", "url": "https://git.sr.ht/~owner/repo", "ref": "main"}),
+        );
+        let pinned = flake_node(
+            json!({"type": "github", "owner": "owner", "repo": "repo", "rev": REV, "narHash": EMPTY_SRI}),
+            json!({"type": "github", "owner": "owner", "repo": "repo", "rev": REV}),
+        );
+
+        assert!(matches!(
+            convert_flake_input(&unknown_host),
+            Err(ImportSourceError::UnknownProvider(_))
+        ));
+        assert!(matches!(
+            convert_flake_input(&pinned),
+            Err(ImportSourceError::Unconvertible(_))
+        ));
+    }
+
+    #[test]
+    fn flake_tarballs_are_static() {
+        let node = flake_node(
+            json!({"type": "tarball", "url": "https://example.com/tool.tar.gz", "narHash": EMPTY_SRI}),
+            json!({"type": "tarball", "url": "https://example.com/tool.tar.gz"}),
+        );
+        let source = convert_flake_input(&node).unwrap();
+
+        assert!(matches!(
+            source.update_scheme,
+            VersionUpdateScheme::Static { unpack: true }
+        ));
+        assert_eq!(
+            source.artifact_url_template,
+            "https://example.com/tool.tar.gz"
+        );
+    }
+}
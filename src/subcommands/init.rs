@@ -1,6 +1,7 @@
+use crate::source::SourceMap;
 use log::{error, info};
 use std::fs::File;
-use std::io::{ErrorKind, Write};
+use std::io::ErrorKind;
 use std::process::ExitCode;
 
 pub fn init(source_file_path: &str) -> ExitCode {
@@ -16,7 +17,7 @@ pub fn init(source_file_path: &str) -> ExitCode {
         }
     };
 
-    if let Err(e) = source_file.write_all("{}".as_bytes()) {
+    if let Err(e) = SourceMap::default().write_to_writer_pretty(&mut source_file) {
         error!("unexpected io error: {e}");
         ExitCode::FAILURE
     } else {
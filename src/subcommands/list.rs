@@ -0,0 +1,123 @@
+use crate::source::SourceMap;
+use crate::updater::VersionUpdateScheme;
+use clap::Args;
+use log::error;
+use serde_json::json;
+use std::process::ExitCode;
+
+#[derive(Args)]
+pub struct ListArgs {
+    /// Only list pinned sources
+    #[arg(long, conflicts_with = "unpinned")]
+    pinned: bool,
+    /// Only list sources that are not pinned
+    #[arg(long)]
+    unpinned: bool,
+    /// Only list sources using this update scheme
+    #[arg(long, value_parser = VersionUpdateScheme::TYPE_NAMES)]
+    scheme: Option<String>,
+    /// Only list sources whose latest checked version differs from their current version
+    #[arg(long)]
+    outdated: bool,
+    /// Print the sources as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+pub fn list(source_file_path: &str, args: ListArgs) -> ExitCode {
+    let sources = match SourceMap::from_file_json(source_file_path) {
+        Ok(s) => s,
+        Err(e) => {
+            error!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let rows = sources
+        .inner
+        .iter()
+        .filter(|(_, source)| !args.pinned || source.pinned)
+        .filter(|(_, source)| !args.unpinned || !source.pinned)
+        .filter(|(_, source)| {
+            args.scheme
+                .as_ref()
+                .is_none_or(|scheme| source.update_scheme.type_name() == scheme)
+        })
+        .filter(|(_, source)| !args.outdated || source.latest_checked_version != source.version)
+        .collect::<Vec<_>>();
+
+    if args.json {
+        let rows = rows
+            .iter()
+            .map(|(name, source)| {
+                json!({
+                    "name": name,
+                    "scheme": source.update_scheme.type_name(),
+                    "version": source.version,
+                    "latest_checked_version": source.latest_checked_version,
+                    "pinned": source.pinned,
+                    "ignore_versions": source.ignore_versions,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        match serde_json::to_string_pretty(&rows) {
+            Ok(json) => println!("{json}"),
+            Err(e) => {
+                error!("could not serialize sources: {e}");
+                return ExitCode::FAILURE;
+            }
+        }
+
+        return ExitCode::SUCCESS;
+    }
+
+    let table = rows
+        .iter()
+        .map(|(name, source)| {
+            let ignored = source
+                .ignore_versions
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+
+            [
+                name.to_string(),
+                source.update_scheme.type_name().to_string(),
+                source.version.clone(),
+                source.latest_checked_version.clone(),
+                if source.pinned { "yes" } else { "no" }.to_string(),
+                ignored.join(", "),
+            ]
+        })
+        .collect::<Vec<_>>();
+    let header = [
+        "NAME",
+        "SCHEME",
+        "VERSION",
+        "LATEST CHECKED",
+        "PINNED",
+        "IGNORED",
+    ]
+    .map(String::from);
+
+    let widths = header.each_ref().map(|cell| cell.len());
+    let widths = table.iter().fold(widths, |mut widths, row| {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+        widths
+    });
+
+    for row in std::iter::once(&header).chain(&table) {
+        let line = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        println!("{}", line.trim_end());
+    }
+
+    ExitCode::SUCCESS
+}
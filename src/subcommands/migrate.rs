@@ -0,0 +1,74 @@
+use crate::migrations::CURRENT_SCHEMA_VERSION;
+use crate::source::{SourceFileLock, SourceMap};
+use clap::Args;
+use log::{error, info};
+use similar::TextDiff;
+use std::fs;
+use std::process::ExitCode;
+
+#[derive(Args)]
+pub struct MigrateArgs {
+    /// Print the changes the migration would make instead of writing them
+    #[arg(long)]
+    dry_run: bool,
+}
+
+pub fn migrate(source_file_path: &str, args: MigrateArgs) -> ExitCode {
+    let _lock = match SourceFileLock::acquire(source_file_path) {
+        Ok(l) => l,
+        Err(e) => {
+            error!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let (sources, schema_version) = match SourceMap::from_file_json_with_version(source_file_path) {
+        Ok(s) => s,
+        Err(e) => {
+            error!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if schema_version == CURRENT_SCHEMA_VERSION {
+        info!("source file is already at schema version {CURRENT_SCHEMA_VERSION}");
+        return ExitCode::SUCCESS;
+    }
+
+    if args.dry_run {
+        let original = match fs::read_to_string(source_file_path) {
+            Ok(s) => s,
+            Err(e) => {
+                error!("could not read source file: {e}");
+                return ExitCode::FAILURE;
+            }
+        };
+
+        let mut migrated = Vec::new();
+        if let Err(e) = sources.write_to_writer_pretty(&mut migrated) {
+            error!("could not serialize migrated source file: {e}");
+            return ExitCode::FAILURE;
+        }
+        let migrated = String::from_utf8_lossy(&migrated);
+
+        print!(
+            "{}",
+            TextDiff::from_lines(original.as_str(), &migrated)
+                .unified_diff()
+                .header(source_file_path, source_file_path)
+        );
+        info!(
+            "source file would be migrated from schema version {schema_version} to {CURRENT_SCHEMA_VERSION}"
+        );
+        return ExitCode::SUCCESS;
+    }
+
+    if let Err(e) = sources.write_to_file(source_file_path) {
+        error!("{e}");
+        return ExitCode::FAILURE;
+    }
+
+    info!("migrated source file from schema version {schema_version} to {CURRENT_SCHEMA_VERSION}");
+
+    ExitCode::SUCCESS
+}
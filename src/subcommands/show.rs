@@ -0,0 +1,177 @@
+use crate::source::{ArtifactKey, SourceMap};
+use clap::Args;
+use log::error;
+use serde_json::{Map, Value};
+use std::process::ExitCode;
+
+#[derive(Args)]
+pub struct ShowArgs {
+    /// Name of the source to show
+    source_name: String,
+    /// Print the source as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+pub fn show(source_file_path: &str, args: ShowArgs) -> ExitCode {
+    let sources = match SourceMap::from_file_json(source_file_path) {
+        Ok(s) => s,
+        Err(e) => {
+            error!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let Some(source) = sources.inner.get(&args.source_name) else {
+        error!("a source named {} does not exist", args.source_name);
+        return ExitCode::FAILURE;
+    };
+
+    // Failures to resolve URLs are shown rather than treated as errors,
+    // since inspecting a broken source is a reason to use this command
+    let full_url = source
+        .full_url(&source.version)
+        .map(|url| url.to_string())
+        .map_err(|e| e.to_string());
+    let artifact_urls = source.artifact_urls(&source.version).unwrap_or_default();
+    let urls_of = |of_key: fn(&ArtifactKey) -> Option<&String>| {
+        artifact_urls
+            .iter()
+            .filter_map(|artifact| {
+                Some((
+                    of_key(&artifact.key)?.clone(),
+                    Value::from(artifact.url.to_string()),
+                ))
+            })
+            .collect::<Map<_, _>>()
+    };
+    let system_urls = urls_of(|key| match key {
+        ArtifactKey::System(system) => Some(system),
+        _ => None,
+    });
+    let named_urls = urls_of(|key| match key {
+        ArtifactKey::Named(name) => Some(name),
+        _ => None,
+    });
+    let git_url = source
+        .update_scheme
+        .git_url(&source.artifact_url_template)
+        .map(|url| url.map(|url| url.to_string()).map_err(|e| e.to_string()));
+
+    let fields = match serde_json::to_value(source) {
+        Ok(Value::Object(fields)) => fields,
+        Ok(_) => unreachable!("sources serialize to objects"),
+        Err(e) => {
+            error!("could not serialize source: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if args.json {
+        let mut fields = fields;
+        fields.insert("name".to_string(), args.source_name.into());
+        fields.insert("full_url".to_string(), full_url.ok().into());
+        if !system_urls.is_empty() {
+            fields.insert("system_urls".to_string(), system_urls.into());
+        }
+        if !named_urls.is_empty() {
+            fields.insert("artifact_urls".to_string(), named_urls.into());
+        }
+        fields.insert("git_url".to_string(), git_url.and_then(Result::ok).into());
+
+        match serde_json::to_string_pretty(&fields) {
+            Ok(json) => println!("{json}"),
+            Err(e) => {
+                error!("could not serialize source: {e}");
+                return ExitCode::FAILURE;
+            }
+        }
+
+        return ExitCode::SUCCESS;
+    }
+
+    println!("name: {}", args.source_name);
+    println!("version: {}", source.version);
+    println!("latest checked version: {}", source.latest_checked_version);
+    if source.systems.is_empty() {
+        println!("hash: {}", source.hash);
+    }
+    if let Some(integrity) = &source.integrity {
+        println!("registry integrity: {integrity}");
+    }
+    if let Some(rev) = &source.rev {
+        println!("rev: {rev}");
+    }
+    println!("pinned: {}", source.pinned);
+    if let Some(minimum_age) = source.minimum_age {
+        println!("minimum age: {minimum_age}");
+    }
+    if !source.ignore_versions.is_empty() {
+        let ignored = source
+            .ignore_versions
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        println!("ignored versions: {}", ignored.join(", "));
+    }
+    println!("artifact URL template: {}", source.artifact_url_template);
+    println!("update scheme: {}", source.update_scheme.type_name());
+    if let Some(Value::Object(scheme)) = fields.get("update_scheme") {
+        for (key, value) in scheme {
+            if key != "type" && !value.is_null() {
+                println!("  {}: {}", key.replace('_', " "), display_value(value));
+            }
+        }
+    }
+
+    if !source.systems.is_empty() {
+        println!("systems:");
+        for (system, artifact) in &source.systems {
+            println!("  {system}:");
+            println!("    arch: {}", artifact.arch);
+            println!("    os: {}", artifact.os);
+            println!("    hash: {}", artifact.hash);
+            if let Some(url) = system_urls.get(system) {
+                println!("    full URL: {}", display_value(url));
+            }
+        }
+    }
+
+    if !source.artifacts.is_empty() {
+        println!("artifacts:");
+        for (artifact_name, artifact) in &source.artifacts {
+            println!("  {artifact_name}:");
+            println!("    URL template: {}", artifact.url_template);
+            println!("    unpack: {}", artifact.unpack);
+            println!("    optional: {}", artifact.optional);
+            if artifact.hash.is_empty() {
+                println!("    hash: <missing>");
+            } else {
+                println!("    hash: {}", artifact.hash);
+            }
+            if let Some(url) = named_urls.get(artifact_name) {
+                println!("    full URL: {}", display_value(url));
+            }
+        }
+    }
+
+    match full_url {
+        Ok(_) if !source.systems.is_empty() => {}
+        Ok(url) => println!("full URL: {url}"),
+        Err(e) => println!("full URL: <{e}>"),
+    }
+    match git_url {
+        Some(Ok(url)) => println!("git URL: {url}"),
+        Some(Err(e)) => println!("git URL: <could not infer: {e}>"),
+        None => {}
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn display_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
@@ -1,11 +1,29 @@
-use crate::source::{get_artifact_hash_from_url, GetArtifactHashError, SourceMap};
+use crate::age::{age_of, format_age, MinimumAge};
+use crate::crates_io::FetchLatestCrateError;
+use crate::feed::FetchLatestFeedVersionError;
+use crate::http_json::FetchHttpJsonVersionError;
+use crate::http_regex::FetchLatestPageVersionError;
+use crate::nix_expr::write_nix_file;
+use crate::npm::FetchLatestNpmVersionError;
+use crate::parallel::map_ordered;
+use crate::prefetch::HashBackend;
+use crate::pypi::FetchLatestPypiReleaseError;
+use crate::releases::FetchLatestReleaseError;
+use crate::source::{
+    check_integrity, get_artifact_hashes, ArtifactHashes, ArtifactUrl, BuildFullUrlError,
+    GetArtifactHashError, MissingArtifacts, Source, SourceFileLock, SourceMap,
+};
 use crate::updater::{FetchLatestGitTagError, GetLatestVersionError};
 use clap::Args;
 use indexmap::IndexMap;
 use log::{debug, error, info, warn};
 use serde::Serialize;
 use std::fmt;
+use std::num::NonZeroUsize;
+use std::ops::ControlFlow;
+use std::path::Path;
 use std::process::ExitCode;
+use std::time::Duration;
 
 #[derive(Args)]
 pub struct UpdateArgs {
@@ -26,10 +44,30 @@ pub struct UpdateArgs {
     /// If any stdout outputs are used, output it as JSON
     #[arg(short, long)]
     pub json: bool,
+    /// Also regenerate this nix file (see `export nix`) whenever sources change
+    #[arg(long, value_name = "FILE")]
+    pub export_nix: Option<String>,
+    /// Check for updates and print them without modifying any files,
+    /// exiting with status 3 if any updates are available
+    #[arg(short = 'n', long)]
+    pub dry_run: bool,
+    /// With '--dry-run', only check for new versions without fetching their artifacts
+    #[arg(long, requires = "dry_run", conflicts_with = "refetch")]
+    pub no_fetch: bool,
+    /// Number of sources to check and fetch concurrently
+    #[arg(short = 'J', long, value_name = "N", default_value = "1")]
+    pub jobs: NonZeroUsize,
+    /// Only adopt versions published at least this long ago, e.g. '3d', '12h' or '1w',
+    /// for sources that don't set their own minimum age
+    #[arg(long, value_name = "AGE")]
+    pub minimum_age: Option<MinimumAge>,
     #[command(flatten)]
     pin: UpdatePinArgs,
 }
 
+/// Exit status of a dry run that found updates, distinct from errors.
+const UPDATES_AVAILABLE: u8 = 3;
+
 #[derive(Args)]
 #[group(required = false, multiple = false)]
 pub struct UpdatePinArgs {
@@ -83,8 +121,134 @@ impl Default for UpdatedSources {
     }
 }
 
-pub fn update(source_file_path: &str, args: UpdateArgs) -> ExitCode {
-    if args.json && !args.show_updated {
+/// The result of checking a single source for updates,
+/// computed without modifying the source so that checks can run in parallel.
+enum SourceCheck {
+    VersionError(GetLatestVersionError),
+    UpToDate,
+    /// A newer version was found, but its artifact was not fetched
+    NewVersion(String),
+    /// A newer version was found, but it isn't old enough to be adopted yet
+    TooNew {
+        latest_tag: String,
+        /// Time since the version was published, if known
+        age: Option<Duration>,
+        minimum_age: MinimumAge,
+    },
+    BrokenUrl(BuildFullUrlError),
+    Fetched {
+        latest_tag: String,
+        integrity: Option<String>,
+        /// URL of the artifact reported along with the version, replacing the template
+        artifact_url: Option<String>,
+        /// Commit the version was built from, for sources following a branch
+        rev: Option<String>,
+        urls: Vec<ArtifactUrl>,
+        hashes: Result<(ArtifactHashes, MissingArtifacts), GetArtifactHashError>,
+    },
+}
+
+fn check_source(
+    source: &Source,
+    refetch: bool,
+    fetch: bool,
+    default_minimum_age: Option<MinimumAge>,
+    hash_backend: HashBackend,
+) -> SourceCheck {
+    let minimum_age = source
+        .minimum_age
+        .or(default_minimum_age)
+        .filter(|age| !age.is_zero() && source.update_scheme.has_publish_dates());
+
+    let (latest_tag, published_at, integrity, artifact_url, rev) = match source
+        .update_scheme
+        .get_latest_release_for(source, minimum_age.is_some())
+    {
+        Ok(latest) => (
+            latest.version,
+            latest.published_at,
+            latest.integrity,
+            latest.artifact_url,
+            latest.rev,
+        ),
+        Err(e) => return SourceCheck::VersionError(e),
+    };
+
+    // A version that was too new when last checked is recorded as checked,
+    // so with a minimum age it has to be looked at again until it is adopted;
+    // a reported artifact URL that differs from the current one is fetched regardless,
+    // as is a reported commit while none is recorded, so that it gets recorded along with its hash
+    if !source.update_scheme.is_static()
+        && !refetch
+        && source.latest_checked_version == latest_tag
+        && (minimum_age.is_none() || source.version == latest_tag)
+        && artifact_url
+            .as_ref()
+            .is_none_or(|url| *url == source.artifact_url_template)
+        && (rev.is_none() || source.rev.is_some())
+    {
+        return SourceCheck::UpToDate;
+    }
+
+    if let Some(minimum_age) = minimum_age.filter(|_| source.version != latest_tag) {
+        if !published_at.is_some_and(|date| minimum_age.is_reached_by(date)) {
+            return SourceCheck::TooNew {
+                latest_tag,
+                age: published_at.map(age_of),
+                minimum_age,
+            };
+        }
+    }
+
+    if !fetch {
+        return if source.version == latest_tag {
+            SourceCheck::UpToDate
+        } else {
+            SourceCheck::NewVersion(latest_tag)
+        };
+    }
+
+    // A reported artifact URL is fetched as is, in place of the template,
+    // and a reported commit in place of the recorded one
+    let urls = match (&artifact_url, &rev) {
+        (None, None) => source.artifact_urls(&latest_tag),
+        _ => Source {
+            artifact_url_template: artifact_url
+                .clone()
+                .unwrap_or_else(|| source.artifact_url_template.clone()),
+            rev: rev.clone().or_else(|| source.rev.clone()),
+            ..source.clone()
+        }
+        .artifact_urls(&latest_tag),
+    };
+    let urls = match urls {
+        Ok(urls) => urls,
+        Err(e) => return SourceCheck::BrokenUrl(e),
+    };
+
+    // Every required artifact has to be fetched for the version to be updated,
+    // and match the integrity published by the registry, if any
+    let hashes = get_artifact_hashes(&urls, hash_backend).and_then(|fetched| {
+        if let Some(integrity) = &integrity {
+            check_integrity(&urls, &fetched.0, integrity)?;
+        }
+        Ok(fetched)
+    });
+
+    SourceCheck::Fetched {
+        latest_tag,
+        integrity,
+        artifact_url,
+        rev,
+        urls,
+        hashes,
+    }
+}
+
+pub fn update(source_file_path: &str, hash_backend: HashBackend, args: UpdateArgs) -> ExitCode {
+    let show_updated = args.show_updated || args.dry_run;
+
+    if args.json && !show_updated {
         warn!("'--json' was passed, but '--show-updated' is not set");
         warn!("the option will do nothing");
     }
@@ -97,6 +261,20 @@ pub fn update(source_file_path: &str, args: UpdateArgs) -> ExitCode {
         return ExitCode::FAILURE;
     }
 
+    // A dry run never writes, so it doesn't need to wait for other processes
+    let _lock = if args.dry_run {
+        info!("dry run; no files will be modified");
+        None
+    } else {
+        match SourceFileLock::acquire(source_file_path) {
+            Ok(l) => Some(l),
+            Err(e) => {
+                error!("{e}");
+                return ExitCode::FAILURE;
+            }
+        }
+    };
+
     let mut sources = match SourceMap::from_file_json(source_file_path) {
         Ok(s) => s,
         Err(e) => {
@@ -117,28 +295,27 @@ pub fn update(source_file_path: &str, args: UpdateArgs) -> ExitCode {
     let mut skipped = 0;
     let mut errors = 0;
 
-    for (name, source) in sources
+    let selected_sources = sources
         .inner
         .iter_mut()
-        .filter(|(name, _)| source_filter.is_empty() || source_filter.contains(name))
-    {
-        if args.pin.pin {
-            if source.pinned {
-                info!("source {name} is already pinned");
-                up_to_date += 1;
-            } else {
-                source.pinned = true;
-                info!("source {name} has been pinned");
-                updated.inner.insert(
-                    name.to_string(),
-                    VersionDiff::new(source.version.clone(), source.version.clone()),
-                );
-                changed = true;
-            }
-            continue;
-        }
-        if args.pin.unpin {
-            if !source.pinned {
+        .filter(|(name, _)| source_filter.is_empty() || source_filter.contains(name));
+
+    if args.pin.pin || args.pin.unpin {
+        for (name, source) in selected_sources {
+            if args.pin.pin {
+                if source.pinned {
+                    info!("source {name} is already pinned");
+                    up_to_date += 1;
+                } else {
+                    source.pinned = true;
+                    info!("source {name} has been pinned");
+                    updated.inner.insert(
+                        name.to_string(),
+                        VersionDiff::new(source.version.clone(), source.version.clone()),
+                    );
+                    changed = true;
+                }
+            } else if !source.pinned {
                 info!("source {name} is already unpinned");
                 up_to_date += 1;
             } else {
@@ -150,139 +327,291 @@ pub fn update(source_file_path: &str, args: UpdateArgs) -> ExitCode {
                 );
                 changed = true;
             }
-            continue;
         }
+    } else {
+        let to_check = selected_sources
+            .map(|(name, source)| (name.clone(), source.clone()))
+            .collect::<Vec<_>>();
 
-        if source.pinned && !args.force {
-            info!("source {name} is pinned; skipping");
-            skipped += 1;
-            continue;
-        }
+        let flow = map_ordered(
+            &to_check,
+            args.jobs,
+            |(name, source)| {
+                (
+                    name.clone(),
+                    (!source.pinned || args.force).then(|| {
+                        check_source(
+                            source,
+                            args.refetch,
+                            !args.no_fetch,
+                            args.minimum_age,
+                            hash_backend,
+                        )
+                    }),
+                )
+            },
+            |(name, check)| {
+                let source = sources
+                    .inner
+                    .get_mut(&name)
+                    .expect("checked sources come from the source map");
 
-        info!("checking new versions for source: {name}");
-        let latest_tag = match source.update_scheme.get_new_version_for(source) {
-            Ok(tag) => tag,
-            Err(e) => match e {
-                GetLatestVersionError::GetGitUrl(e) => {
-                    error!("{name}: could not infer git repository url: {e}");
-                    error!("git_url may need to be set manually; if so, re-add this source with the correct options");
-                    warn!("skipping source {name} with an error");
-                    skipped += 1;
-                    errors += 1;
-                    continue;
-                }
-                GetLatestVersionError::FetchGitTags {
-                    error: FetchLatestGitTagError::NoTagsFitFilter,
-                    tag_prefix,
-                } => {
-                    error!(
-                        "{name}: no tags found fit the tag prefix `{}`",
-                        tag_prefix.as_deref().unwrap_or("")
-                    );
-                    error!("tag_prefix may be set incorrectly; if so, re-add this source with the correct options");
-                    warn!("skipping source {name} with an error");
+                let Some(check) = check else {
+                    info!("source {name} is pinned; skipping");
                     skipped += 1;
-                    errors += 1;
-                    continue;
-                }
-                _ => {
-                    error!("{name}: failed to fetch new version for source: {e}");
-                    error!("critical error encountered; aborting update");
-                    return ExitCode::FAILURE;
-                }
-            },
-        };
+                    return ControlFlow::Continue(());
+                };
 
-        if !source.update_scheme.is_static()
-            && !args.refetch
-            && source.latest_checked_version == latest_tag
-        {
-            info!("{name} is up to date (version {})", source.version);
-            up_to_date += 1;
-            continue;
-        }
+                info!("checking new versions for source: {name}");
+                let (latest_tag, integrity, artifact_url, rev, urls, hashes) = match check {
+                    SourceCheck::VersionError(e) => {
+                        match e {
+                            GetLatestVersionError::GetGitUrl(e) => {
+                                error!("{name}: could not infer git repository url: {e}");
+                                error!("git_url may need to be set manually; if so, set it with `nix-kunai edit --git-repo`");
+                            }
+                            GetLatestVersionError::FetchGitTags {
+                                error: FetchLatestGitTagError::NoTagsFitFilter,
+                                filter,
+                            } => {
+                                error!("{name}: no tags found fit the {filter}");
+                                error!("the tag filter may be set incorrectly; if so, fix it with `nix-kunai edit`");
+                            }
+                            GetLatestVersionError::FetchReleases {
+                                error: FetchLatestReleaseError::NoMatchingRelease,
+                                filter,
+                            } => {
+                                error!("{name}: no releases found fit the {filter} and have a matching asset");
+                                error!("tag_prefix or the artifact URL may be set incorrectly; if so, fix it with `nix-kunai edit`");
+                            }
+                            GetLatestVersionError::FetchPypiReleases {
+                                error: FetchLatestPypiReleaseError::NoMatchingRelease,
+                                package,
+                            } => {
+                                error!("{name}: no releases of {package} found fit and have a matching file");
+                                error!("the package or the artifact URL may be set incorrectly; if so, fix it with `nix-kunai edit`");
+                            }
+                            GetLatestVersionError::FetchCrateVersions {
+                                error: FetchLatestCrateError::NoMatchingVersion,
+                                package,
+                            } => {
+                                error!("{name}: no versions of crate {package} found fit");
+                                error!("every version may be yanked or ignored; if the source shouldn't follow them, fix it with `nix-kunai edit`");
+                            }
+                            GetLatestVersionError::FetchNpmVersions {
+                                error:
+                                    error @ (FetchLatestNpmVersionError::DistTagNotFound(_)
+                                    | FetchLatestNpmVersionError::DistTagIgnored { .. }
+                                    | FetchLatestNpmVersionError::NoMatchingVersion),
+                                package,
+                            } => {
+                                error!("{name}: npm package {package}: {error}");
+                                error!("the dist-tag or version constraint may be set incorrectly; if so, fix it with `nix-kunai edit`");
+                            }
+                            GetLatestVersionError::FetchHttpJsonVersion {
+                                error:
+                                    error @ (FetchHttpJsonVersionError::PathNotFound(_)
+                                    | FetchHttpJsonVersionError::NotAString { .. }),
+                                endpoint,
+                            } => {
+                                error!("{name}: {endpoint}: {error}");
+                                error!("the response may have changed shape; if so, fix the paths with `nix-kunai edit --version-path` or `--artifact-url-path`");
+                            }
+                            GetLatestVersionError::FetchPageVersions {
+                                error: FetchLatestPageVersionError::NoMatchingVersion,
+                                page_url,
+                            } => {
+                                error!("{name}: no versions found on {page_url} fit");
+                                error!("the page may have changed; if so, fix the version pattern with `nix-kunai edit --version-pattern`");
+                            }
+                            GetLatestVersionError::FetchFeedVersions {
+                                error: FetchLatestFeedVersionError::NoMatchingVersion,
+                                feed_url,
+                            } => {
+                                error!("{name}: no entries of feed {feed_url} found with a version that fits");
+                                error!("the feed may have changed; if so, fix the version pattern with `nix-kunai edit --version-pattern` or `--version-from`");
+                            }
+                            GetLatestVersionError::FetchTagDate { .. } => {
+                                error!("{name}: {e}");
+                                error!("the date is needed to check the minimum age; if it can't be fetched, remove the minimum age from the source or the command");
+                            }
+                            // Rate limits in particular only affect sources on the same host
+                            _ if e.http_status().is_some() => {
+                                error!("{name}: {e}");
+                                error!("the host may be rate limiting requests (setting GITHUB_TOKEN, GITLAB_TOKEN or GITEA_TOKEN raises the limits of their APIs), or the source may have moved");
+                            }
+                            _ => {
+                                error!("{name}: failed to fetch new version for source: {e}");
+                                error!("critical error encountered; aborting update");
+                                return ControlFlow::Break(());
+                            }
+                        }
+                        warn!("skipping source {name} with an error");
+                        skipped += 1;
+                        errors += 1;
+                        return ControlFlow::Continue(());
+                    }
 
-        let full_url = match source.full_url(&latest_tag) {
-            Ok(url) => url,
-            Err(e) => {
-                error!("{e}");
-                error!("this usually implies that the artifact URL template is broken; fix it or remove the offending source");
-                warn!("skipping source {name} with an error");
-                skipped += 1;
-                errors += 1;
-                continue;
-            }
-        };
+                    SourceCheck::UpToDate => {
+                        info!("{name} is up to date (version {})", source.version);
+                        up_to_date += 1;
+                        return ControlFlow::Continue(());
+                    }
 
-        info!(
-            "{}fetching hash from {full_url}",
-            if source.version == latest_tag && args.refetch {
-                "re"
-            } else {
-                ""
-            }
-        );
-        match get_artifact_hash_from_url(&full_url, source.update_scheme.unpack()) {
-            Ok(hash) => {
-                if source.version != latest_tag {
-                    info!("{name} updated: {} -> {}", source.version, latest_tag);
-                    updated.inner.insert(
-                        name.to_string(),
-                        VersionDiff::new(source.version.clone(), latest_tag.clone()),
-                    );
-                    source.hash = hash;
-                    source.version = latest_tag.clone();
-                } else if source.hash != hash {
-                    if source.update_scheme.is_static() {
+                    SourceCheck::NewVersion(latest_tag) => {
                         info!(
-                            "updated hash for source {name} with static version {}",
+                            "{name} has a new version: {} -> {latest_tag}",
                             source.version
                         );
-                    } else {
-                        info!("hash for source {name} changed, but with the same version (version {})", source.version);
+                        updated.inner.insert(
+                            name.to_string(),
+                            VersionDiff::new(source.version.clone(), latest_tag),
+                        );
+                        return ControlFlow::Continue(());
                     }
-                    updated.inner.insert(
-                        name.to_string(),
-                        VersionDiff::new(source.version.clone(), latest_tag.clone()),
-                    );
-                    source.hash = hash;
-                } else {
-                    info!(
-                        "{name} is up to date (same hash) (version {})",
-                        source.version
-                    );
-                    up_to_date += 1;
-                }
-                source.latest_checked_version = latest_tag;
-                changed = true;
-            }
 
-            Err(e) => {
-                match e {
-                    GetArtifactHashError::PrefetchFailed { .. } => {
-                        warn!(
-                            "{name}: found newer tag {latest_tag} (> {}), but {e}",
-                            source.version
-                        );
-                        warn!("either non-release tag or artifact name changed; if the latter, re-add this source with the new artifact URL");
-                        warn!("version will not be updated; source is considered skipped with an error");
+                    SourceCheck::TooNew {
+                        latest_tag,
+                        age,
+                        minimum_age,
+                    } => {
+                        match age {
+                            Some(age) => info!(
+                                "{name}: {latest_tag} was published {} ago, younger than the minimum age of {minimum_age}",
+                                format_age(age)
+                            ),
+                            None => warn!(
+                                "{name}: {latest_tag} has no known publish date, so its minimum age of {minimum_age} can't be checked"
+                            ),
+                        }
+                        info!("{name} will stay at version {} for now", source.version);
                         source.latest_checked_version = latest_tag;
                         skipped += 1;
-                        errors += 1;
                         changed = true;
+                        return ControlFlow::Continue(());
                     }
-                    _ => {
-                        error!("{name}: unexpected error: {e}");
-                        error!("skipping source; the command may have to be rerun");
+
+                    SourceCheck::BrokenUrl(e) => {
+                        error!("{e}");
+                        error!("this usually implies that the artifact URL template is broken; fix it or remove the offending source");
+                        warn!("skipping source {name} with an error");
                         skipped += 1;
                         errors += 1;
+                        return ControlFlow::Continue(());
                     }
+
+                    SourceCheck::Fetched {
+                        latest_tag,
+                        integrity,
+                        artifact_url,
+                        rev,
+                        urls,
+                        hashes,
+                    } => (latest_tag, integrity, artifact_url, rev, urls, hashes),
+                };
+
+                for artifact in &urls {
+                    info!(
+                        "{}fetching hash from {}",
+                        if source.version == latest_tag && args.refetch {
+                            "re"
+                        } else {
+                            ""
+                        },
+                        artifact.url
+                    );
                 }
-            }
+                match hashes {
+                    Ok((hashes, missing)) => {
+                        for (key, e) in missing {
+                            warn!("{name}: optional {key} is missing at {latest_tag}: {e}");
+                        }
+                        if let Some(artifact_url) = artifact_url {
+                            if artifact_url != source.artifact_url_template {
+                                info!("{name}: artifact URL changed to {artifact_url}");
+                                source.artifact_url_template = artifact_url;
+                            }
+                        }
+                        // The recorded commit is the one the artifacts were just fetched at
+                        if rev.is_some() {
+                            source.rev = rev;
+                        }
+
+                        if source.version != latest_tag {
+                            info!("{name} updated: {} -> {}", source.version, latest_tag);
+                            updated.inner.insert(
+                                name.to_string(),
+                                VersionDiff::new(source.version.clone(), latest_tag.clone()),
+                            );
+                            source.set_hashes(hashes);
+                            source.integrity = integrity;
+                            source.version = latest_tag.clone();
+                        } else if source.hashes_differ(&hashes) {
+                            if source.update_scheme.is_static() {
+                                info!(
+                                    "updated hash for source {name} with static version {}",
+                                    source.version
+                                );
+                            } else {
+                                info!("hash for source {name} changed, but with the same version (version {})", source.version);
+                            }
+                            updated.inner.insert(
+                                name.to_string(),
+                                VersionDiff::new(source.version.clone(), latest_tag.clone()),
+                            );
+                            source.set_hashes(hashes);
+                            source.integrity = integrity;
+                        } else {
+                            info!(
+                                "{name} is up to date (same hash) (version {})",
+                                source.version
+                            );
+                            up_to_date += 1;
+                        }
+                        source.latest_checked_version = latest_tag;
+                        changed = true;
+                    }
+
+                    Err(e) => match e {
+                        GetArtifactHashError::PrefetchFailed { .. } => {
+                            warn!(
+                                "{name}: found newer tag {latest_tag} (> {}), but {e}",
+                                source.version
+                            );
+                            warn!("either non-release tag or artifact name changed; if the latter, set the new artifact URL with `nix-kunai edit --artifact-url` (or `--add-artifact` for a named artifact)");
+                            warn!("version will not be updated; source is considered skipped with an error");
+                            source.latest_checked_version = latest_tag;
+                            skipped += 1;
+                            errors += 1;
+                            changed = true;
+                        }
+                        GetArtifactHashError::IntegrityMismatch { .. } => {
+                            error!("{name}: {e}");
+                            error!("the registry may be serving a different file than it published; version will not be updated");
+                            skipped += 1;
+                            errors += 1;
+                        }
+                        _ => {
+                            error!("{name}: unexpected error: {e}");
+                            error!("skipping source; the command may have to be rerun");
+                            skipped += 1;
+                            errors += 1;
+                        }
+                    },
+                }
+
+                ControlFlow::Continue(())
+            },
+        );
+
+        if flow.is_break() {
+            return ExitCode::FAILURE;
         }
     }
 
-    if changed {
+    if args.dry_run {
+        debug!("dry run; will not write to file");
+    } else if changed {
         if let Err(e) = sources.write_to_file(source_file_path) {
             error!("{e}");
             return ExitCode::FAILURE;
@@ -291,13 +620,28 @@ pub fn update(source_file_path: &str, args: UpdateArgs) -> ExitCode {
         debug!("no changes were made, will not write to file");
     }
 
+    if let (Some(nix_path), false) = (&args.export_nix, args.dry_run) {
+        if changed || !Path::new(nix_path).exists() {
+            if let Err(e) = write_nix_file(&sources, nix_path) {
+                error!("{e}");
+                return ExitCode::FAILURE;
+            }
+            debug!("exported sources to {nix_path}");
+        }
+    }
+
     if !args.pin.pin && !args.pin.unpin {
         info!(
-            "successfully updated {} source(s) ({skipped} skipped ({errors} with errors), {up_to_date} already up to date)",
+            "{} {} source(s) ({skipped} skipped ({errors} with errors), {up_to_date} already up to date)",
+            if args.dry_run {
+                "found updates for"
+            } else {
+                "successfully updated"
+            },
             updated.inner.len()
         );
 
-        if args.show_updated {
+        if show_updated {
             if args.json {
                 use std::io::{stdout, Write};
 
@@ -306,7 +650,8 @@ pub fn update(source_file_path: &str, args: UpdateArgs) -> ExitCode {
                 writeln!(&mut lock).unwrap();
             } else if !updated.inner.is_empty() {
                 println!(
-                    "Updated packages: {}",
+                    "{} packages: {}",
+                    if args.dry_run { "Outdated" } else { "Updated" },
                     updated
                         .inner
                         .iter()
@@ -325,11 +670,16 @@ pub fn update(source_file_path: &str, args: UpdateArgs) -> ExitCode {
         let pin = if args.pin.pin { "pin" } else { "unpin" };
 
         info!(
-            "successfully {pin}ned {} source(s) ({up_to_date} already {pin}ned)",
+            "{} {pin}ned {} source(s) ({up_to_date} already {pin}ned)",
+            if args.dry_run {
+                "would have"
+            } else {
+                "successfully"
+            },
             updated.inner.len()
         );
 
-        if args.show_updated {
+        if show_updated {
             if args.json {
                 use std::io::{stdout, Write};
 
@@ -359,5 +709,11 @@ pub fn update(source_file_path: &str, args: UpdateArgs) -> ExitCode {
             }
         }
     }
+
+    // Sources that would be pinned or unpinned aren't updates
+    if args.dry_run && !args.pin.pin && !args.pin.unpin && !updated.inner.is_empty() {
+        return ExitCode::from(UPDATES_AVAILABLE);
+    }
+
     ExitCode::SUCCESS
 }
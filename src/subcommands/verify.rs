@@ -0,0 +1,146 @@
+use crate::parallel::map_ordered;
+use crate::prefetch::HashBackend;
+use crate::source::{get_artifact_hash_from_url, SourceMap};
+use crate::updater::VersionUpdateScheme;
+use clap::Args;
+use log::{error, info, warn};
+use std::num::NonZeroUsize;
+use std::ops::ControlFlow;
+use std::process::ExitCode;
+
+#[derive(Args)]
+pub struct VerifyArgs {
+    /// Specific sources to verify
+    #[arg(value_name = "SOURCES")]
+    source_names: Vec<String>,
+    /// Number of sources to fetch concurrently
+    #[arg(short = 'J', long, value_name = "N", default_value = "1")]
+    jobs: NonZeroUsize,
+}
+
+pub fn verify(source_file_path: &str, hash_backend: HashBackend, args: VerifyArgs) -> ExitCode {
+    let sources = match SourceMap::from_file_json(source_file_path) {
+        Ok(s) => s,
+        Err(e) => {
+            error!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let source_filter = args.source_names;
+    for source_name in source_filter.iter() {
+        if !sources.inner.contains_key(source_name) {
+            warn!("source {source_name} does not exist, skipping");
+        }
+    }
+
+    let mut skipped = 0;
+    let to_verify = sources
+        .inner
+        .iter()
+        .filter(|(name, _)| source_filter.is_empty() || source_filter.contains(name))
+        // Without a recorded commit, the archive of a branch can only be fetched at its current head,
+        // which doesn't match the recorded hash once the branch has moved on
+        .filter(|(name, source)| {
+            let untracked_branch = matches!(source.update_scheme, VersionUpdateScheme::GitBranch { .. })
+                && source.rev.is_none();
+            if untracked_branch {
+                warn!("{name}: no commit is recorded for the branch, so its archive can't be verified; skipping");
+                warn!("run `nix-kunai update {name}` to record it");
+                skipped += 1;
+            }
+            !untracked_branch
+        })
+        .collect::<Vec<_>>();
+
+    let mut verified = 0;
+    let mut mismatched = Vec::new();
+    let mut errors = 0;
+
+    let _ = map_ordered(
+        &to_verify,
+        args.jobs,
+        |&(name, source)| {
+            let result = source.artifact_urls(&source.version).map(|urls| {
+                urls.into_iter()
+                    // Optional artifacts that were missing have nothing to verify
+                    .filter(|artifact| {
+                        !artifact.optional
+                            || source.hash_of(&artifact.key).is_some_and(|h| !h.is_empty())
+                    })
+                    .map(|artifact| {
+                        let hash = get_artifact_hash_from_url(
+                            &artifact.url,
+                            artifact.unpack,
+                            hash_backend,
+                        );
+                        (artifact.key, artifact.url, hash)
+                    })
+                    .collect::<Vec<_>>()
+            });
+            (name, source, result)
+        },
+        |(name, source, result)| {
+            let artifacts = match result {
+                Ok(artifacts) => artifacts,
+                Err(e) => {
+                    error!("{name}: {e}");
+                    errors += 1;
+                    return ControlFlow::Continue(());
+                }
+            };
+
+            let mut matches = true;
+            let mut failed = false;
+            for (key, url, hash) in artifacts {
+                let recorded = source.hash_of(&key).unwrap_or_default();
+                match hash {
+                    Ok(hash) if hash == recorded => {}
+                    Ok(hash) => {
+                        error!(
+                            "{name}: hash mismatch for {url} (version {})",
+                            source.version
+                        );
+                        error!("recorded: {recorded}");
+                        error!("fetched:  {hash}");
+                        matches = false;
+                    }
+                    Err(e) => {
+                        error!("{name}: {e}");
+                        failed = true;
+                    }
+                }
+            }
+
+            if !matches {
+                mismatched.push(name.as_str());
+            } else if failed {
+                errors += 1;
+            } else {
+                info!(
+                    "{name} matches its recorded hash (version {})",
+                    source.version
+                );
+                verified += 1;
+            }
+
+            ControlFlow::Continue(())
+        },
+    );
+
+    info!(
+        "{verified} of {} source(s) match their recorded hash ({} mismatched, {errors} with errors, {skipped} skipped)",
+        to_verify.len() + skipped,
+        mismatched.len()
+    );
+
+    if !mismatched.is_empty() {
+        error!("mismatched sources: {}", mismatched.join(", "));
+    }
+
+    if mismatched.is_empty() && errors == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,540 @@
+use clap::ValueEnum;
+use regex::Regex;
+use semver::{Prerelease, Version, VersionReq};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Name of the capture group in a tag pattern that holds the version.
+const VERSION_GROUP: &str = "version";
+
+/// A regex that has to match a whole tag, with a capture group named `version`.
+#[derive(Clone)]
+pub struct TagPattern {
+    pattern: String,
+    regex: Regex,
+}
+
+#[derive(Debug, Error)]
+pub enum ParseTagPatternError {
+    #[error("invalid regex: {0}")]
+    Regex(#[from] regex::Error),
+    #[error(
+        "pattern has no capture group named `{VERSION_GROUP}`, e.g. `(?<{VERSION_GROUP}>...)`"
+    )]
+    NoVersionGroup,
+}
+
+impl FromStr for TagPattern {
+    type Err = ParseTagPatternError;
+
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        let regex = Regex::new(&format!("^(?:{pattern})$"))?;
+        if !regex
+            .capture_names()
+            .any(|name| name == Some(VERSION_GROUP))
+        {
+            return Err(ParseTagPatternError::NoVersionGroup);
+        }
+
+        Ok(Self {
+            pattern: pattern.to_string(),
+            regex,
+        })
+    }
+}
+
+impl fmt::Display for TagPattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.pattern)
+    }
+}
+
+impl Serialize for TagPattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.pattern)
+    }
+}
+
+impl<'de> Deserialize<'de> for TagPattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// A regex found anywhere in a text, such as a download page,
+/// with a capture group named `version`.
+#[derive(Clone)]
+pub struct VersionPattern {
+    pattern: String,
+    regex: Regex,
+}
+
+impl VersionPattern {
+    /// The versions captured by every match of the pattern in `text`.
+    pub fn versions_in<'t>(&self, text: &'t str) -> impl Iterator<Item = &'t str> + use<'_, 't> {
+        self.regex
+            .captures_iter(text)
+            .filter_map(|captures| Some(captures.name(VERSION_GROUP)?.as_str()))
+    }
+}
+
+impl FromStr for VersionPattern {
+    type Err = ParseTagPatternError;
+
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        let regex = Regex::new(pattern)?;
+        if !regex
+            .capture_names()
+            .any(|name| name == Some(VERSION_GROUP))
+        {
+            return Err(ParseTagPatternError::NoVersionGroup);
+        }
+
+        Ok(Self {
+            pattern: pattern.to_string(),
+            regex,
+        })
+    }
+}
+
+impl fmt::Display for VersionPattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.pattern)
+    }
+}
+
+impl Serialize for VersionPattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.pattern)
+    }
+}
+
+impl<'de> Deserialize<'de> for VersionPattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// A replacement applied to versions extracted from tags, e.g. `_` to `.` for tags like `1_2_3`.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct VersionReplacement {
+    pub from: String,
+    pub to: String,
+}
+
+impl FromStr for VersionReplacement {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((from, to)) if !from.is_empty() => Ok(Self {
+                from: from.to_string(),
+                to: to.to_string(),
+            }),
+            _ => Err("expected FROM=TO, where FROM is not empty".to_string()),
+        }
+    }
+}
+
+impl fmt::Display for VersionReplacement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}={}", self.from, self.to)
+    }
+}
+
+/// A version to skip, either exact (e.g. `2.1.0`) or a glob (e.g. `2.1.*`),
+/// where `*` matches any run of characters and `?` any single character.
+#[derive(Clone)]
+pub struct VersionGlob {
+    glob: String,
+    regex: Regex,
+}
+
+impl VersionGlob {
+    pub fn matches(&self, version: &str) -> bool {
+        self.regex.is_match(version)
+    }
+}
+
+impl FromStr for VersionGlob {
+    type Err = regex::Error;
+
+    fn from_str(glob: &str) -> Result<Self, Self::Err> {
+        let pattern = glob
+            .split('*')
+            .map(|part| {
+                part.split('?')
+                    .map(regex::escape)
+                    .collect::<Vec<_>>()
+                    .join(".")
+            })
+            .collect::<Vec<_>>()
+            .join(".*");
+
+        Ok(Self {
+            glob: glob.to_string(),
+            regex: Regex::new(&format!("^{pattern}$"))?,
+        })
+    }
+}
+
+impl PartialEq for VersionGlob {
+    fn eq(&self, other: &Self) -> bool {
+        self.glob == other.glob
+    }
+}
+
+impl fmt::Display for VersionGlob {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.glob)
+    }
+}
+
+impl Serialize for VersionGlob {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.glob)
+    }
+}
+
+impl<'de> Deserialize<'de> for VersionGlob {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Which versions are followed, depending on whether they are prereleases.
+#[derive(Clone, Copy, Default, Deserialize, PartialEq, Serialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum PrereleasePolicy {
+    /// Only follow stable versions
+    #[default]
+    Exclude,
+    /// Follow both stable versions and prereleases
+    Include,
+    /// Only follow prereleases
+    Only,
+}
+
+impl PrereleasePolicy {
+    fn allows(self, prerelease: bool) -> bool {
+        match self {
+            Self::Exclude => !prerelease,
+            Self::Include => true,
+            Self::Only => prerelease,
+        }
+    }
+}
+
+/// Words that mark a tag as a prerelease when they appear in it as a whole word,
+/// e.g. `2.0.0-rc1`, `v2.0.0.beta.3` or `nightly-2024-01-01`.
+const PRERELEASE_MARKERS: [&str; 14] = [
+    "alpha",
+    "beta",
+    "rc",
+    "pre",
+    "prerelease",
+    "preview",
+    "dev",
+    "nightly",
+    "snapshot",
+    "canary",
+    "unstable",
+    "insiders",
+    "next",
+    "experimental",
+];
+
+/// Whether a tag looks like a prerelease,
+/// meaning one of its runs of letters is a prerelease marker (ignoring case).
+pub fn is_prerelease(tag: &str) -> bool {
+    tag.split(|c: char| !c.is_ascii_alphabetic()).any(|word| {
+        PRERELEASE_MARKERS
+            .iter()
+            .any(|marker| word.eq_ignore_ascii_case(marker))
+    })
+}
+
+/// Decides which tags are followed, and how their versions are extracted.
+#[derive(Clone, Copy, Default)]
+pub struct TagFilter<'a> {
+    /// Prefix stripped from tags; the rest has to start with a digit.
+    /// Ignored if a pattern is set.
+    pub prefix: Option<&'a str>,
+    pub pattern: Option<&'a TagPattern>,
+    pub replacement: Option<&'a VersionReplacement>,
+    pub constraint: Option<&'a VersionReq>,
+    pub prereleases: PrereleasePolicy,
+    /// Versions that are skipped, so that the next best one is followed instead
+    pub ignored: &'a [VersionGlob],
+}
+
+impl TagFilter<'_> {
+    /// Extracts the version from a tag, or returns `None` if the tag isn't followed.
+    pub fn version_from_tag(&self, tag: &str) -> Option<String> {
+        self.version_from_release_tag(tag, false)
+    }
+
+    /// Like `version_from_tag`, for the tag of a release its host may have flagged as a prerelease.
+    pub fn version_from_release_tag(&self, tag: &str, flagged_prerelease: bool) -> Option<String> {
+        if !self
+            .prereleases
+            .allows(flagged_prerelease || is_prerelease(tag))
+        {
+            return None;
+        }
+
+        let version = match self.pattern {
+            Some(pattern) => pattern.regex.captures(tag)?.name(VERSION_GROUP)?.as_str(),
+            None => tag
+                .strip_prefix(self.prefix.unwrap_or(""))
+                .filter(|version| version.starts_with(|c: char| c.is_ascii_digit()))?,
+        };
+
+        let version = match self.replacement {
+            Some(replacement) => version.replace(&replacement.from, &replacement.to),
+            None => version.to_string(),
+        };
+
+        if self.ignored.iter().any(|glob| glob.matches(&version)) {
+            return None;
+        }
+
+        if let Some(constraint) = self.constraint {
+            let mut parsed = parse_version(&version)?;
+            // Semver requirements never match prereleases of other versions,
+            // so compare their release part instead when prereleases are followed
+            if self.prereleases != PrereleasePolicy::Exclude {
+                parsed.pre = Prerelease::EMPTY;
+            }
+            if !constraint.matches(&parsed) {
+                return None;
+            }
+        }
+
+        Some(version)
+    }
+}
+
+impl fmt::Display for TagFilter<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.pattern {
+            Some(pattern) => write!(f, "tag pattern `{pattern}`")?,
+            None => write!(f, "tag prefix `{}`", self.prefix.unwrap_or(""))?,
+        }
+        if let Some(constraint) = self.constraint {
+            write!(f, " and version constraint `{constraint}`")?;
+        }
+        match self.prereleases {
+            PrereleasePolicy::Exclude => {}
+            PrereleasePolicy::Include => write!(f, " (including prereleases)")?,
+            PrereleasePolicy::Only => write!(f, " (prereleases only)")?,
+        }
+        if !self.ignored.is_empty() {
+            let ignored = self
+                .ignored
+                .iter()
+                .map(|glob| format!("`{glob}`"))
+                .collect::<Vec<_>>();
+            write!(f, ", ignoring versions {}", ignored.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses a version as semver, leniently accepting versions with a missing minor or patch number
+/// (e.g. `1.4` or `2`), which are padded with zeros.
+pub fn parse_version(version: &str) -> Option<Version> {
+    if let Ok(version) = Version::parse(version) {
+        return Some(version);
+    }
+
+    let version = version
+        .split_once('+')
+        .map_or(version, |(version, _)| version);
+    let (core, pre) = match version.split_once('-') {
+        Some((core, pre)) => (core, Some(pre)),
+        None => (version, None),
+    };
+
+    let mut numbers = core.split('.').map(str::parse::<u64>);
+    let major = numbers.next()?.ok()?;
+    let minor = numbers.next().transpose().ok()?.unwrap_or(0);
+    let patch = numbers.next().transpose().ok()?.unwrap_or(0);
+    if numbers.next().is_some() {
+        return None;
+    }
+
+    let mut version = Version::new(major, minor, patch);
+    if let Some(pre) = pre {
+        version.pre = Prerelease::new(pre).ok()?;
+    }
+
+    Some(version)
+}
+
+/// Compares versions the way `git-tags` sources have their tags sorted by git
+/// (`--sort=v:refname` with `versionsort.suffix=-`): runs of digits compare as numbers,
+/// and at the first difference, a `-` sorts first, so that `1.0-rc1` comes before `1.0`.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let offset = a.iter().zip(b).take_while(|(a, b)| a == b).count();
+
+    match (a.get(offset), b.get(offset)) {
+        (None, None) => return Ordering::Equal,
+        (Some(b'-'), Some(_) | None) => return Ordering::Less,
+        (Some(_) | None, Some(b'-')) => return Ordering::Greater,
+        _ => {}
+    }
+
+    // A difference within a number compares the whole numbers, e.g. `1.9` against `1.10`
+    let start = offset
+        - a[..offset]
+            .iter()
+            .rev()
+            .take_while(|c| c.is_ascii_digit())
+            .count();
+    let number = |s: &[u8]| {
+        let digits = s[start..].iter().take_while(|c| c.is_ascii_digit()).count();
+        let number = &s[start..start + digits];
+        let leading_zeros = number.iter().take_while(|c| **c == b'0').count();
+        number[leading_zeros.min(digits.saturating_sub(1))..].to_vec()
+    };
+    let (number_a, number_b) = (number(a), number(b));
+    if !number_a.is_empty() && !number_b.is_empty() {
+        let ordering = number_a
+            .len()
+            .cmp(&number_b.len())
+            .then_with(|| number_a.cmp(&number_b));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+
+    a[offset..].cmp(&b[offset..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prerelease_markers_are_whole_words() {
+        assert!(is_prerelease("v2.0.0-rc1"));
+        assert!(is_prerelease("2.0.0.BETA.3"));
+        assert!(is_prerelease("nightly-2024-01-01"));
+        assert!(!is_prerelease("v2.0.0"));
+        // Markers inside other words don't count
+        assert!(!is_prerelease("preact-10.0.0"));
+        assert!(!is_prerelease("devtools-1.0.0"));
+    }
+
+    #[test]
+    fn prefix_is_stripped_and_needs_a_digit() {
+        let filter = TagFilter {
+            prefix: Some("v"),
+            ..Default::default()
+        };
+
+        assert_eq!(filter.version_from_tag("v1.2.3").as_deref(), Some("1.2.3"));
+        assert_eq!(filter.version_from_tag("1.2.3"), None);
+        assert_eq!(filter.version_from_tag("vnext"), None);
+    }
+
+    #[test]
+    fn pattern_and_replacement_extract_the_version() {
+        let pattern = "release-(?<version>[0-9_]+)".parse::<TagPattern>().unwrap();
+        let replacement = "_=.".parse::<VersionReplacement>().unwrap();
+        let filter = TagFilter {
+            pattern: Some(&pattern),
+            replacement: Some(&replacement),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            filter.version_from_tag("release-1_2_3").as_deref(),
+            Some("1.2.3")
+        );
+        // The pattern has to match the whole tag
+        assert_eq!(filter.version_from_tag("release-1_2_3-extra"), None);
+    }
+
+    #[test]
+    fn prerelease_policy_applies_to_flagged_releases() {
+        let exclude = TagFilter::default();
+        let only = TagFilter {
+            prereleases: PrereleasePolicy::Only,
+            ..Default::default()
+        };
+
+        assert_eq!(exclude.version_from_release_tag("2.0.0", true), None);
+        assert_eq!(exclude.version_from_release_tag("2.0.0-rc1", false), None);
+        assert_eq!(
+            exclude.version_from_release_tag("2.0.0", false).as_deref(),
+            Some("2.0.0")
+        );
+        assert_eq!(
+            only.version_from_release_tag("2.0.0", true).as_deref(),
+            Some("2.0.0")
+        );
+        assert_eq!(only.version_from_release_tag("2.0.0", false), None);
+    }
+
+    #[test]
+    fn constraint_compares_release_part_of_prereleases() {
+        let constraint = VersionReq::parse("^2.0").unwrap();
+        let exclude = TagFilter {
+            constraint: Some(&constraint),
+            ..Default::default()
+        };
+        let include = TagFilter {
+            prereleases: PrereleasePolicy::Include,
+            ..exclude
+        };
+
+        assert_eq!(exclude.version_from_tag("2.1").as_deref(), Some("2.1"));
+        assert_eq!(exclude.version_from_tag("3.0.0"), None);
+        assert_eq!(
+            include.version_from_tag("2.1.0-rc1").as_deref(),
+            Some("2.1.0-rc1")
+        );
+    }
+
+    #[test]
+    fn ignored_versions_are_skipped() {
+        let ignored = ["2.1.*".parse::<VersionGlob>().unwrap()];
+        let filter = TagFilter {
+            ignored: &ignored,
+            ..Default::default()
+        };
+
+        assert_eq!(filter.version_from_tag("2.1.4"), None);
+        assert_eq!(filter.version_from_tag("2.10.0").as_deref(), Some("2.10.0"));
+    }
+
+    #[test]
+    fn versions_compare_numerically() {
+        assert_eq!(compare_versions("1.9", "1.10"), Ordering::Less);
+        assert_eq!(compare_versions("1.10.0", "1.9.9"), Ordering::Greater);
+        // Equal numbers fall back to comparing their text
+        assert_eq!(compare_versions("1.02", "1.2"), Ordering::Less);
+        assert_eq!(compare_versions("2.0", "2.0"), Ordering::Equal);
+    }
+
+    #[test]
+    fn dash_suffixes_sort_first() {
+        assert_eq!(compare_versions("1.0-rc1", "1.0"), Ordering::Less);
+        assert_eq!(compare_versions("1.0", "1.0-rc1"), Ordering::Greater);
+        assert_eq!(compare_versions("1.0-rc1", "1.0-rc2"), Ordering::Less);
+        assert_eq!(compare_versions("1.0-rc2", "1.0.1"), Ordering::Less);
+    }
+}
@@ -1,4 +1,17 @@
+use crate::crates_io::{fetch_latest_crate_version, FetchLatestCrateError};
+use crate::feed::{fetch_latest_feed_version, FeedField, FetchLatestFeedVersionError};
+use crate::http::HttpGetError;
+use crate::http_json::{fetch_http_json_version, FetchHttpJsonVersionError, JsonPath};
+use crate::http_regex::{fetch_latest_page_version, FetchLatestPageVersionError};
+use crate::npm::{
+    fetch_latest_npm_version, FetchLatestNpmVersionError, NpmVersionSelector, DEFAULT_DIST_TAG,
+};
+use crate::pypi::{fetch_latest_pypi_release, FetchLatestPypiReleaseError};
+use crate::releases::{fetch_latest_release, FetchLatestReleaseError, GitProvider, LatestRelease};
 use crate::source::Source;
+use crate::tags::{PrereleasePolicy, TagFilter, TagPattern, VersionPattern, VersionReplacement};
+use jiff::Timestamp;
+use semver::VersionReq;
 use serde::{Deserialize, Serialize};
 use std::io;
 use std::num::NonZeroUsize;
@@ -13,12 +26,110 @@ pub enum VersionUpdateScheme {
         unpack: bool,
         repo_url: Option<Url>,
         tag_prefix: Option<String>,
+        /// Regex matching whole tags, with a `version` capture group; overrides `tag_prefix`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tag_pattern: Option<TagPattern>,
+        /// Replacement applied to extracted versions, e.g. `_` to `.`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        version_replacement: Option<VersionReplacement>,
+        /// Only follow versions matching this semver requirement, e.g. `^1.4`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        version_constraint: Option<VersionReq>,
+        #[serde(default)]
+        prereleases: PrereleasePolicy,
+    },
+    GithubReleases {
+        unpack: bool,
+        repo_url: Option<Url>,
+        tag_prefix: Option<String>,
+        api_url: Option<Url>,
+        #[serde(default)]
+        prereleases: PrereleasePolicy,
+    },
+    GitlabReleases {
+        unpack: bool,
+        repo_url: Option<Url>,
+        tag_prefix: Option<String>,
+        api_url: Option<Url>,
+        #[serde(default)]
+        prereleases: PrereleasePolicy,
+    },
+    GiteaReleases {
+        unpack: bool,
+        repo_url: Option<Url>,
+        tag_prefix: Option<String>,
+        api_url: Option<Url>,
+        #[serde(default)]
+        prereleases: PrereleasePolicy,
     },
     GitBranch {
         repo_url: Url,
         branch: String,
         short_hash_length: NonZeroUsize,
     },
+    Pypi {
+        unpack: bool,
+        package: String,
+        /// Base URL of a PyPI-compatible JSON API, e.g. a local mirror; defaults to PyPI
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        registry_url: Option<Url>,
+        #[serde(default)]
+        prereleases: PrereleasePolicy,
+    },
+    CratesIo {
+        unpack: bool,
+        package: String,
+        /// Base URL of a sparse registry index; defaults to the one of crates.io
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        registry_url: Option<Url>,
+        #[serde(default)]
+        prereleases: PrereleasePolicy,
+    },
+    Npm {
+        unpack: bool,
+        package: String,
+        /// Base URL of an npm-compatible registry; defaults to the npm registry
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        registry_url: Option<Url>,
+        /// Dist-tag to follow, e.g. `next`; defaults to `latest`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        dist_tag: Option<String>,
+        /// Follow the newest version matching this semver range instead of a dist-tag
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        version_constraint: Option<VersionReq>,
+    },
+    HttpJson {
+        unpack: bool,
+        /// URL of a JSON endpoint reporting the latest version, e.g. a vendor's update API
+        endpoint: Url,
+        /// Path to the version in the response, e.g. `$.releases[0].version`
+        version_path: JsonPath,
+        /// Path to the URL of the artifact in the response, which then replaces the artifact URL
+        /// template on every update
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        artifact_url_path: Option<JsonPath>,
+    },
+    HttpRegex {
+        unpack: bool,
+        /// URL of a web page listing versions, e.g. a download page
+        page_url: Url,
+        /// Regex found anywhere on the page, with a `version` capture group
+        version_pattern: VersionPattern,
+        #[serde(default)]
+        prereleases: PrereleasePolicy,
+    },
+    Feed {
+        unpack: bool,
+        /// URL of an RSS or Atom feed with an entry per release, e.g. GitHub's `releases.atom`
+        feed_url: Url,
+        /// Regex finding the version of an entry, with a `version` capture group
+        version_pattern: VersionPattern,
+        /// Whether versions are found in the titles or the links of entries
+        #[serde(default)]
+        version_from: FeedField,
+        #[serde(default)]
+        prereleases: PrereleasePolicy,
+    },
     Static {
         unpack: bool,
     },
@@ -31,33 +142,330 @@ pub enum GetLatestVersionError {
     #[error("failed to fetch tags for source: {error}")]
     FetchGitTags {
         error: FetchLatestGitTagError,
-        tag_prefix: Option<String>,
+        /// Description of the tag filter, for error messages
+        filter: String,
+    },
+    #[error("failed to fetch releases for source: {error}")]
+    FetchReleases {
+        error: FetchLatestReleaseError,
+        /// Description of the tag filter, for error messages
+        filter: String,
+    },
+    #[error("failed to fetch releases of PyPI package {package}: {error}")]
+    FetchPypiReleases {
+        error: FetchLatestPypiReleaseError,
+        package: String,
+    },
+    #[error("failed to fetch versions of crate {package}: {error}")]
+    FetchCrateVersions {
+        error: FetchLatestCrateError,
+        package: String,
+    },
+    #[error("failed to fetch versions of npm package {package}: {error}")]
+    FetchNpmVersions {
+        error: FetchLatestNpmVersionError,
+        package: String,
+    },
+    #[error("failed to get version from {endpoint}: {error}")]
+    FetchHttpJsonVersion {
+        error: FetchHttpJsonVersionError,
+        endpoint: String,
+    },
+    #[error("failed to get versions from {page_url}: {error}")]
+    FetchPageVersions {
+        error: FetchLatestPageVersionError,
+        page_url: String,
+    },
+    #[error("failed to get versions from feed {feed_url}: {error}")]
+    FetchFeedVersions {
+        error: FetchLatestFeedVersionError,
+        feed_url: String,
     },
     #[error("failed to get commit for branch {branch}: {error}")]
     FetchBranchCommit {
         error: FetchGitBranchCommitError,
         branch: String,
     },
+    #[error("failed to get date of tag {tag}: {error}")]
+    FetchTagDate {
+        error: FetchGitTagDateError,
+        tag: String,
+    },
+}
+
+impl GetLatestVersionError {
+    /// The failed request behind the error, if the host responded with an error status,
+    /// e.g. because it rate limits requests or the package was removed.
+    pub fn http_status(&self) -> Option<&HttpGetError> {
+        let error = match self {
+            Self::FetchReleases {
+                error: FetchLatestReleaseError::Http(error),
+                ..
+            }
+            | Self::FetchPypiReleases {
+                error: FetchLatestPypiReleaseError::Http(error),
+                ..
+            }
+            | Self::FetchCrateVersions {
+                error: FetchLatestCrateError::Http(error),
+                ..
+            }
+            | Self::FetchNpmVersions {
+                error: FetchLatestNpmVersionError::Http(error),
+                ..
+            }
+            | Self::FetchHttpJsonVersion {
+                error: FetchHttpJsonVersionError::Http(error),
+                ..
+            }
+            | Self::FetchPageVersions {
+                error: FetchLatestPageVersionError::Http(error),
+                ..
+            }
+            | Self::FetchFeedVersions {
+                error: FetchLatestFeedVersionError::Http(error),
+                ..
+            } => error,
+            _ => return None,
+        };
+
+        matches!(error, HttpGetError::Status { .. }).then_some(error)
+    }
 }
 
 impl VersionUpdateScheme {
     pub fn get_new_version_for(&self, source: &Source) -> Result<String, GetLatestVersionError> {
+        self.get_latest_release_for(source, false)
+            .map(|latest| latest.version)
+    }
+
+    /// Gets the latest version like `get_new_version_for`, along with when it was published:
+    /// the commit date of its tag for `git-tags`, and the publish date for release schemes,
+    /// registries and feeds.
+    /// Registries that publish the integrity of their artifacts (`npm`) also report it,
+    /// and `http-json` reports the URL of the artifact if it has a path to one.
+    ///
+    /// The date is `None` for schemes without publish dates, if the host doesn't report one,
+    /// or if `fetch_date` is false; release dates come with the releases,
+    /// but tag dates require fetching the tagged commit,
+    /// which is skipped if the version is the source's current one.
+    pub fn get_latest_release_for(
+        &self,
+        source: &Source,
+        fetch_date: bool,
+    ) -> Result<LatestRelease, GetLatestVersionError> {
         match self {
-            Self::GitTags {
-                repo_url,
-                tag_prefix,
-                ..
-            } => {
+            Self::GitTags { repo_url, .. } => {
                 let git_url = repo_url.as_ref().map_or_else(
                     || infer_git_url(&source.artifact_url_template),
                     |url| Ok(url.clone()),
                 )?;
+                let filter = TagFilter {
+                    ignored: &source.ignore_versions,
+                    ..self.tag_filter()
+                };
 
-                fetch_latest_git_tag(&git_url, tag_prefix.as_deref()).map_err(|error| {
+                let latest = fetch_latest_git_tag(&git_url, &filter).map_err(|error| {
                     GetLatestVersionError::FetchGitTags {
                         error,
-                        tag_prefix: tag_prefix.clone(),
+                        filter: filter.to_string(),
                     }
+                })?;
+
+                if !fetch_date || latest.version == source.version {
+                    return Ok(LatestRelease {
+                        version: latest.version,
+                        published_at: None,
+                        integrity: None,
+                        artifact_url: None,
+                        rev: None,
+                    });
+                }
+
+                let date = fetch_git_tag_date(&git_url, &latest.tag).map_err(|error| {
+                    GetLatestVersionError::FetchTagDate {
+                        error,
+                        tag: latest.tag,
+                    }
+                })?;
+
+                Ok(LatestRelease {
+                    version: latest.version,
+                    published_at: Some(date),
+                    integrity: None,
+                    artifact_url: None,
+                    rev: None,
+                })
+            }
+
+            Self::GithubReleases {
+                repo_url, api_url, ..
+            }
+            | Self::GitlabReleases {
+                repo_url, api_url, ..
+            }
+            | Self::GiteaReleases {
+                repo_url, api_url, ..
+            } => {
+                let git_url = repo_url.as_ref().map_or_else(
+                    || infer_git_url(&source.artifact_url_template),
+                    |url| Ok(url.clone()),
+                )?;
+                let provider = self.release_provider().expect("scheme is a release scheme");
+                let filter = TagFilter {
+                    ignored: &source.ignore_versions,
+                    ..self.tag_filter()
+                };
+
+                let latest = fetch_latest_release(
+                    provider,
+                    api_url.as_ref(),
+                    &git_url,
+                    &filter,
+                    |version| source.artifact_names(version),
+                )
+                .map_err(|error| GetLatestVersionError::FetchReleases {
+                    error,
+                    filter: filter.to_string(),
+                })?;
+
+                Ok(LatestRelease {
+                    published_at: latest.published_at.filter(|_| fetch_date),
+                    ..latest
+                })
+            }
+
+            Self::Pypi {
+                package,
+                registry_url,
+                ..
+            } => {
+                let filter = TagFilter {
+                    ignored: &source.ignore_versions,
+                    ..self.tag_filter()
+                };
+
+                let latest =
+                    fetch_latest_pypi_release(registry_url.as_ref(), package, &filter, |version| {
+                        source.artifact_names(version)
+                    })
+                    .map_err(|error| {
+                        GetLatestVersionError::FetchPypiReleases {
+                            error,
+                            package: package.clone(),
+                        }
+                    })?;
+
+                Ok(LatestRelease {
+                    published_at: latest.published_at.filter(|_| fetch_date),
+                    ..latest
+                })
+            }
+
+            Self::CratesIo {
+                package,
+                registry_url,
+                ..
+            } => {
+                let filter = TagFilter {
+                    ignored: &source.ignore_versions,
+                    ..self.tag_filter()
+                };
+
+                let latest = fetch_latest_crate_version(registry_url.as_ref(), package, &filter)
+                    .map_err(|error| GetLatestVersionError::FetchCrateVersions {
+                        error,
+                        package: package.clone(),
+                    })?;
+
+                Ok(LatestRelease {
+                    published_at: latest.published_at.filter(|_| fetch_date),
+                    ..latest
+                })
+            }
+
+            Self::Npm {
+                package,
+                registry_url,
+                dist_tag,
+                version_constraint,
+                ..
+            } => {
+                let selector = match version_constraint {
+                    Some(constraint) => NpmVersionSelector::Constraint(constraint),
+                    None => {
+                        NpmVersionSelector::DistTag(dist_tag.as_deref().unwrap_or(DEFAULT_DIST_TAG))
+                    }
+                };
+
+                let latest = fetch_latest_npm_version(
+                    registry_url.as_ref(),
+                    package,
+                    &selector,
+                    &source.ignore_versions,
+                )
+                .map_err(|error| GetLatestVersionError::FetchNpmVersions {
+                    error,
+                    package: package.clone(),
+                })?
+                .release;
+
+                Ok(LatestRelease {
+                    published_at: latest.published_at.filter(|_| fetch_date),
+                    ..latest
+                })
+            }
+
+            Self::HttpJson {
+                endpoint,
+                version_path,
+                artifact_url_path,
+                ..
+            } => fetch_http_json_version(endpoint, version_path, artifact_url_path.as_ref())
+                .map_err(|error| GetLatestVersionError::FetchHttpJsonVersion {
+                    error,
+                    endpoint: endpoint.to_string(),
+                }),
+
+            Self::HttpRegex {
+                page_url,
+                version_pattern,
+                ..
+            } => {
+                let filter = TagFilter {
+                    ignored: &source.ignore_versions,
+                    ..self.tag_filter()
+                };
+
+                fetch_latest_page_version(page_url, version_pattern, &filter).map_err(|error| {
+                    GetLatestVersionError::FetchPageVersions {
+                        error,
+                        page_url: page_url.to_string(),
+                    }
+                })
+            }
+
+            Self::Feed {
+                feed_url,
+                version_pattern,
+                version_from,
+                ..
+            } => {
+                let filter = TagFilter {
+                    ignored: &source.ignore_versions,
+                    ..self.tag_filter()
+                };
+
+                let latest =
+                    fetch_latest_feed_version(feed_url, version_pattern, *version_from, &filter)
+                        .map_err(|error| GetLatestVersionError::FetchFeedVersions {
+                            error,
+                            feed_url: feed_url.to_string(),
+                        })?;
+
+                Ok(LatestRelease {
+                    published_at: latest.published_at.filter(|_| fetch_date),
+                    ..latest
                 })
             }
 
@@ -67,17 +475,107 @@ impl VersionUpdateScheme {
                 short_hash_length,
                 ..
             } => {
-                let short_hash = fetch_git_branch_commit(repo_url, branch)
-                    .map(|hash| hash[0..(short_hash_length.get())].to_string())
-                    .map_err(|error| GetLatestVersionError::FetchBranchCommit {
+                let commit = fetch_git_branch_commit(repo_url, branch).map_err(|error| {
+                    GetLatestVersionError::FetchBranchCommit {
                         error,
                         branch: branch.clone(),
-                    })?;
+                    }
+                })?;
 
-                Ok(format!("{branch}-{short_hash}"))
+                Ok(LatestRelease {
+                    version: format!("{branch}-{}", &commit[0..(short_hash_length.get())]),
+                    published_at: None,
+                    integrity: None,
+                    artifact_url: None,
+                    rev: Some(commit),
+                })
             }
 
-            Self::Static { .. } => Ok(source.version.clone()),
+            Self::Static { .. } => Ok(LatestRelease {
+                version: source.version.clone(),
+                published_at: None,
+                integrity: None,
+                artifact_url: None,
+                rev: None,
+            }),
+        }
+    }
+
+    /// Whether versions of the scheme have a publish date, which a minimum age can apply to.
+    pub fn has_publish_dates(&self) -> bool {
+        self.follows_versions() && !matches!(self, Self::HttpRegex { .. })
+    }
+
+    /// Whether the scheme follows released versions, from tags, releases, a registry,
+    /// a web page or a feed, and so has versions that can be ignored.
+    pub fn follows_versions(&self) -> bool {
+        matches!(
+            self,
+            Self::GitTags { .. }
+                | Self::GithubReleases { .. }
+                | Self::GitlabReleases { .. }
+                | Self::GiteaReleases { .. }
+                | Self::Pypi { .. }
+                | Self::CratesIo { .. }
+                | Self::Npm { .. }
+                | Self::HttpRegex { .. }
+                | Self::Feed { .. }
+        )
+    }
+
+    /// The names of all schemes, as returned by `type_name`.
+    pub const TYPE_NAMES: [&'static str; 12] = [
+        "git-tags",
+        "github-releases",
+        "gitlab-releases",
+        "gitea-releases",
+        "git-branch",
+        "pypi",
+        "crates-io",
+        "npm",
+        "http-json",
+        "http-regex",
+        "feed",
+        "static",
+    ];
+
+    /// The git repository the scheme follows, inferring it from the artifact URL if not set.
+    /// Returns `None` for schemes that don't follow a repository.
+    pub fn git_url(&self, artifact_url_template: &str) -> Option<Result<Url, InferGitUrlError>> {
+        match self {
+            Self::GitTags { repo_url, .. }
+            | Self::GithubReleases { repo_url, .. }
+            | Self::GitlabReleases { repo_url, .. }
+            | Self::GiteaReleases { repo_url, .. } => Some(repo_url.as_ref().map_or_else(
+                || infer_git_url(artifact_url_template),
+                |url| Ok(url.clone()),
+            )),
+            Self::GitBranch { repo_url, .. } => Some(Ok(repo_url.clone())),
+            Self::Pypi { .. }
+            | Self::CratesIo { .. }
+            | Self::Npm { .. }
+            | Self::HttpJson { .. }
+            | Self::HttpRegex { .. }
+            | Self::Feed { .. }
+            | Self::Static { .. } => None,
+        }
+    }
+
+    /// The name of the scheme, as used in the source file and on the command line.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::GitTags { .. } => "git-tags",
+            Self::GithubReleases { .. } => "github-releases",
+            Self::GitlabReleases { .. } => "gitlab-releases",
+            Self::GiteaReleases { .. } => "gitea-releases",
+            Self::GitBranch { .. } => "git-branch",
+            Self::Pypi { .. } => "pypi",
+            Self::CratesIo { .. } => "crates-io",
+            Self::Npm { .. } => "npm",
+            Self::HttpJson { .. } => "http-json",
+            Self::HttpRegex { .. } => "http-regex",
+            Self::Feed { .. } => "feed",
+            Self::Static { .. } => "static",
         }
     }
 
@@ -86,10 +584,90 @@ impl VersionUpdateScheme {
         matches!(self, Self::Static { .. })
     }
 
+    /// The filter deciding which tags the scheme follows,
+    /// not yet including the versions ignored by the source.
+    pub fn tag_filter(&self) -> TagFilter<'_> {
+        match self {
+            Self::GitTags {
+                tag_prefix,
+                tag_pattern,
+                version_replacement,
+                version_constraint,
+                prereleases,
+                ..
+            } => TagFilter {
+                prefix: tag_prefix.as_deref(),
+                pattern: tag_pattern.as_ref(),
+                replacement: version_replacement.as_ref(),
+                constraint: version_constraint.as_ref(),
+                prereleases: *prereleases,
+                ..Default::default()
+            },
+            Self::GithubReleases {
+                tag_prefix,
+                prereleases,
+                ..
+            }
+            | Self::GitlabReleases {
+                tag_prefix,
+                prereleases,
+                ..
+            }
+            | Self::GiteaReleases {
+                tag_prefix,
+                prereleases,
+                ..
+            } => TagFilter {
+                prefix: tag_prefix.as_deref(),
+                prereleases: *prereleases,
+                ..Default::default()
+            },
+            Self::Pypi { prereleases, .. }
+            | Self::CratesIo { prereleases, .. }
+            | Self::HttpRegex { prereleases, .. }
+            | Self::Feed { prereleases, .. } => TagFilter {
+                prereleases: *prereleases,
+                ..Default::default()
+            },
+            Self::Npm { .. }
+            | Self::HttpJson { .. }
+            | Self::GitBranch { .. }
+            | Self::Static { .. } => TagFilter::default(),
+        }
+    }
+
+    /// The provider whose releases API the scheme follows, if it is a release scheme.
+    pub fn release_provider(&self) -> Option<GitProvider> {
+        match self {
+            Self::GithubReleases { .. } => Some(GitProvider::Github),
+            Self::GitlabReleases { .. } => Some(GitProvider::Gitlab),
+            Self::GiteaReleases { .. } => Some(GitProvider::Gitea),
+            _ => None,
+        }
+    }
+
+    /// The archive type of the main artifacts, for URLs that don't end with an extension
+    /// from which `fetchzip` could tell it.
+    pub fn archive_extension(&self) -> Option<&'static str> {
+        match self {
+            Self::CratesIo { .. } => Some("tar.gz"),
+            _ => None,
+        }
+    }
+
     pub fn unpack(&self) -> bool {
         match self {
             VersionUpdateScheme::GitTags { unpack, .. } => *unpack,
+            VersionUpdateScheme::GithubReleases { unpack, .. }
+            | VersionUpdateScheme::GitlabReleases { unpack, .. }
+            | VersionUpdateScheme::GiteaReleases { unpack, .. } => *unpack,
             VersionUpdateScheme::GitBranch { .. } => true,
+            VersionUpdateScheme::Pypi { unpack, .. }
+            | VersionUpdateScheme::CratesIo { unpack, .. }
+            | VersionUpdateScheme::Npm { unpack, .. }
+            | VersionUpdateScheme::HttpJson { unpack, .. }
+            | VersionUpdateScheme::HttpRegex { unpack, .. }
+            | VersionUpdateScheme::Feed { unpack, .. } => *unpack,
             VersionUpdateScheme::Static { unpack } => *unpack,
         }
     }
@@ -176,10 +754,21 @@ pub enum FetchLatestGitTagError {
     NoTagsFitFilter,
 }
 
+/// The latest tag of a repository that fits a filter, as returned by `fetch_latest_git_tag`.
+pub struct LatestTag {
+    /// The full name of the tag, e.g. `cli/v1.2.3`
+    pub tag: String,
+    pub version: String,
+}
+
+/// Fetches the latest tag of a repository that passes `filter`.
+///
+/// Tag patterns are matched against the full tag name (e.g. `cli/v1.2.3`),
+/// while prefixes are matched against the last path segment only (e.g. `v1.2.3`).
 pub fn fetch_latest_git_tag(
     url: &Url,
-    filter: Option<&str>,
-) -> Result<String, FetchLatestGitTagError> {
+    filter: &TagFilter,
+) -> Result<LatestTag, FetchLatestGitTagError> {
     let args = [
         "-c",
         "versionsort.suffix=-",
@@ -198,26 +787,85 @@ pub fn fetch_latest_git_tag(
 
     let output_string = String::from_utf8(output.stdout)?;
 
-    let filter = filter.unwrap_or("");
-    let latest_tag = output_string
+    output_string
         .lines()
-        .map(|line| line.split('/').next_back().unwrap_or(""))
-        .rfind(|line| {
-            line.starts_with(filter)
-                && !line.ends_with("^{}")
-                && line
-                    .chars()
-                    .nth(filter.len())
-                    .is_some_and(|c| c.is_ascii_digit())
-        });
-
-    let latest_tag = match latest_tag {
-        Some(tag) => tag,
-        None => return Err(FetchLatestGitTagError::NoTagsFitFilter),
+        .filter(|line| !line.ends_with("^{}"))
+        .filter_map(|line| line.split_once("refs/tags/").map(|(_, tag)| tag))
+        .rev()
+        .find_map(|tag| {
+            let matched = match filter.pattern {
+                Some(_) => tag,
+                None => tag.split('/').next_back().unwrap_or(""),
+            };
+            let version = filter.version_from_tag(matched)?;
+
+            Some(LatestTag {
+                tag: tag.to_string(),
+                version,
+            })
+        })
+        .ok_or(FetchLatestGitTagError::NoTagsFitFilter)
+}
+
+#[derive(Debug, Error)]
+pub enum FetchGitTagDateError {
+    #[error("could not create temporary repository: {0}")]
+    TempRepo(io::Error),
+    #[error("failed to execute command: {full_command}")]
+    CommandFailed {
+        full_command: String,
+        io_error: io::Error,
+    },
+    #[error("command `{full_command}` failed: {stderr}")]
+    CommandUnsuccessful {
+        full_command: String,
+        stderr: String,
+    },
+    #[error("could not parse commit date {0}")]
+    InvalidDate(String),
+}
+
+/// Fetches the commit date of a tag by shallowly fetching just the tagged commit
+/// into a temporary repository, since `git ls-remote` doesn't report dates.
+pub fn fetch_git_tag_date(url: &Url, tag: &str) -> Result<Timestamp, FetchGitTagDateError> {
+    let repo = tempfile::tempdir().map_err(FetchGitTagDateError::TempRepo)?;
+    let repo_path = repo.path().to_string_lossy();
+    let tag_ref = format!("refs/tags/{tag}");
+
+    let run = |args: &[&str]| {
+        let full_command = format!("git {}", args.join(" "));
+        let output = Command::new("git").args(args).output().map_err(|e| {
+            FetchGitTagDateError::CommandFailed {
+                full_command: full_command.clone(),
+                io_error: e,
+            }
+        })?;
+
+        if !output.status.success() {
+            return Err(FetchGitTagDateError::CommandUnsuccessful {
+                full_command,
+                stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            });
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
     };
 
-    Ok(latest_tag
-        .strip_prefix(filter)
-        .unwrap_or(latest_tag)
-        .to_string())
+    run(&["init", "--quiet", "--bare", &repo_path])?;
+    run(&[
+        "-C",
+        &repo_path,
+        "fetch",
+        "--quiet",
+        "--depth=1",
+        "--no-tags",
+        url.as_ref(),
+        &tag_ref,
+    ])?;
+    let date = run(&["-C", &repo_path, "log", "-1", "--format=%ct", "FETCH_HEAD"])?;
+
+    date.parse()
+        .ok()
+        .and_then(|secs| Timestamp::from_second(secs).ok())
+        .ok_or(FetchGitTagDateError::InvalidDate(date))
 }